@@ -0,0 +1,298 @@
+//! Derive macro for [`arcy::AsyncDrop`](https://mkmik.github.io/arcy/arcy/trait.AsyncDrop.html).
+//!
+//! Use it through the `derive` feature of `arcy`, which re-exports it as
+//! `arcy::AsyncDrop`.
+
+#![deny(rust_2018_idioms)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, Fields, Ident, LitInt,
+    Result, Type, WherePredicate,
+};
+
+/// Derives `AsyncDrop` for a struct or an enum by async dropping each of its fields.
+///
+/// Fields are async dropped one after the other, in declaration order. Attributes
+/// change that:
+///
+/// * `#[async_drop(order = N)]` on a field: fields with a lower order are dropped
+///   first. Fields have order 0 unless specified.
+/// * `#[async_drop(join)]` on a field: the field is dropped concurrently with the
+///   other `join` fields of the same order. On the type, all fields are `join`.
+/// * `#[async_drop(skip)]` on a field: the field isn't async dropped, it's dropped
+///   synchronously once every other field has been async dropped.
+///
+/// # Examples
+///
+/// ```
+/// use arcy::{Arcy, AsyncDrop};
+///
+/// struct Conn(&'static str);
+///
+/// impl AsyncDrop for Conn {
+///     async fn async_drop(self) {
+///         println!("closing {}", self.0);
+///     }
+/// }
+///
+/// #[derive(AsyncDrop)]
+/// struct Service {
+///     // closed last, once the cache and the queue are flushed
+///     #[async_drop(order = 1)]
+///     db: Conn,
+///     #[async_drop(join)]
+///     cache: Conn,
+///     #[async_drop(join)]
+///     queue: Conn,
+///     #[async_drop(skip)]
+///     name: String,
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (service, last_service) = Arcy::new(Service {
+///     db: Conn("db"),
+///     cache: Conn("cache"),
+///     queue: Conn("queue"),
+///     name: "service".to_string(),
+/// });
+/// drop(service);
+/// assert!(last_service.await.is_completed());
+/// # }
+/// ```
+#[proc_macro_derive(AsyncDrop, attributes(async_drop))]
+pub fn derive_async_drop(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// How a field is dropped.
+struct FieldAttrs {
+    order: i32,
+    join: bool,
+    skip: bool,
+}
+
+/// A field bound by the destructuring pattern of a variant.
+struct Field {
+    binding: Ident,
+    ty: Type,
+    attrs: FieldAttrs,
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let join_all = parse_container_attrs(&input.attrs)?;
+    let name = &input.ident;
+
+    let mut predicates: Vec<WherePredicate> = Vec::new();
+    let mut arms = Vec::new();
+    match &input.data {
+        Data::Struct(data) => {
+            let fields = collect_fields(&data.fields, join_all)?;
+            let pattern = pattern(quote!(#name), &data.fields, &fields);
+            arms.push(arm(pattern, &fields, &mut predicates));
+        }
+        Data::Enum(data) => {
+            for variant in &data.variants {
+                let fields = collect_fields(&variant.fields, join_all)?;
+                let ident = &variant.ident;
+                let pattern = pattern(quote!(#name::#ident), &variant.fields, &fields);
+                arms.push(arm(pattern, &fields, &mut predicates));
+            }
+        }
+        Data::Union(data) => {
+            return Err(Error::new_spanned(
+                data.union_token,
+                "AsyncDrop can't be derived for unions",
+            ))
+        }
+    }
+
+    let mut generics = input.generics.clone();
+    // the fields are held across awaits
+    let params: Vec<Ident> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        where_clause
+            .predicates
+            .push(parse_quote!(#param: ::core::marker::Send));
+    }
+    where_clause.predicates.extend(predicates);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::arcy::AsyncDrop for #name #ty_generics #where_clause {
+            fn async_drop(self) -> impl ::core::future::Future<Output = ()> + ::core::marker::Send {
+                let this = self;
+                async move {
+                    match this {
+                        #(#arms)*
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn parse_container_attrs(attrs: &[Attribute]) -> Result<bool> {
+    let mut join = false;
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("async_drop"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("join") {
+                join = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported async_drop attribute, expected `join`"))
+            }
+        })?;
+    }
+    Ok(join)
+}
+
+fn parse_field_attrs(attrs: &[Attribute], join_all: bool) -> Result<FieldAttrs> {
+    let mut order = None;
+    let mut join = false;
+    let mut skip = false;
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("async_drop"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("order") {
+                let lit: LitInt = meta.value()?.parse()?;
+                order = Some(lit.base10_parse()?);
+            } else if meta.path.is_ident("join") {
+                join = true;
+            } else if meta.path.is_ident("skip") {
+                skip = true;
+            } else {
+                return Err(meta.error(
+                    "unsupported async_drop attribute, expected `order`, `join` or `skip`",
+                ));
+            }
+            Ok(())
+        })?;
+        if skip && (order.is_some() || join) {
+            return Err(Error::new_spanned(
+                attr,
+                "a skipped field can't have an order nor be joined",
+            ));
+        }
+    }
+    Ok(FieldAttrs {
+        order: order.unwrap_or(0),
+        join: !skip && (join || join_all),
+        skip,
+    })
+}
+
+fn collect_fields(fields: &Fields, join_all: bool) -> Result<Vec<Field>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            Ok(Field {
+                // not after the field's name: `_name` would make `__arcy__name`,
+                // which isn't snake case
+                binding: format_ident!("__arcy_field_{}", i),
+                ty: field.ty.clone(),
+                attrs: parse_field_attrs(&field.attrs, join_all)?,
+            })
+        })
+        .collect()
+}
+
+/// Destructures a struct or enum variant, binding every field.
+fn pattern(path: TokenStream2, shape: &Fields, fields: &[Field]) -> TokenStream2 {
+    let bindings = fields.iter().map(|field| &field.binding);
+    match shape {
+        Fields::Named(named) => {
+            let idents = named.named.iter().map(|field| &field.ident);
+            quote!(#path { #(#idents: #bindings),* })
+        }
+        Fields::Unnamed(_) => quote!(#path(#(#bindings),*)),
+        Fields::Unit => quote!(#path),
+    }
+}
+
+/// The match arm dropping the fields of a variant, adding the bounds the fields
+/// need to `predicates`.
+fn arm(
+    pattern: TokenStream2,
+    fields: &[Field],
+    predicates: &mut Vec<WherePredicate>,
+) -> TokenStream2 {
+    // the fields being `Send` is left to the `+ Send` on the returned future:
+    // bounding field types with lifetimes by `Send` doesn't compile
+    for field in fields.iter().filter(|field| !field.attrs.skip) {
+        let ty = &field.ty;
+        predicates.push(parse_quote!(#ty: ::arcy::AsyncDrop));
+    }
+
+    let mut orders: Vec<i32> = fields
+        .iter()
+        .filter(|field| !field.attrs.skip)
+        .map(|field| field.attrs.order)
+        .collect();
+    orders.sort_unstable();
+    orders.dedup();
+
+    let mut steps = Vec::new();
+    for order in orders {
+        let group: Vec<&Field> = fields
+            .iter()
+            .filter(|field| !field.attrs.skip && field.attrs.order == order)
+            .collect();
+        let mut joined = false;
+        for field in &group {
+            if !field.attrs.join {
+                let binding = &field.binding;
+                steps.push(quote!(::arcy::AsyncDrop::async_drop(#binding).await;));
+            } else if !joined {
+                // all the joined fields of the group are dropped where the first one is
+                joined = true;
+                let bindings = group
+                    .iter()
+                    .filter(|field| field.attrs.join)
+                    .map(|field| &field.binding);
+                steps.push(join(bindings));
+            }
+        }
+    }
+
+    let skipped = fields
+        .iter()
+        .filter(|field| field.attrs.skip)
+        .map(|field| &field.binding);
+
+    quote! {
+        #pattern => {
+            #(#steps)*
+            #(::core::mem::drop(#skipped);)*
+        }
+    }
+}
+
+/// Awaits the async drops of the bindings concurrently.
+fn join<'a>(bindings: impl DoubleEndedIterator<Item = &'a Ident>) -> TokenStream2 {
+    let mut bindings = bindings.rev();
+    let last = bindings.next().expect("at least one joined field");
+    let mut future = quote!(::arcy::AsyncDrop::async_drop(#last));
+    for binding in bindings {
+        future = quote! {
+            ::arcy::__private::join(::arcy::AsyncDrop::async_drop(#binding), #future)
+        };
+    }
+    quote!(#future.await;)
+}
@@ -0,0 +1,226 @@
+//! Drop order of the fields of derived `AsyncDrop` impls.
+
+#![deny(warnings)]
+
+use std::sync::{Arc, Mutex};
+
+use arcy::{Arcy, AsyncDrop};
+
+/// What happened, in order.
+#[derive(Clone, Default)]
+struct Log(Arc<Mutex<Vec<String>>>);
+
+impl Log {
+    fn push(&self, event: impl Into<String>) {
+        self.0.lock().unwrap().push(event.into());
+    }
+
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut self.0.lock().unwrap())
+    }
+}
+
+/// Logs the start and the end of its async drop, yielding in between.
+struct Conn(&'static str, Log);
+
+impl AsyncDrop for Conn {
+    async fn async_drop(self) {
+        self.1.push(format!("start {}", self.0));
+        tokio::task::yield_now().await;
+        self.1.push(format!("end {}", self.0));
+    }
+}
+
+/// Logs its synchronous drop.
+struct Sync(&'static str, Log);
+
+impl Drop for Sync {
+    fn drop(&mut self) {
+        self.1.push(format!("drop {}", self.0));
+    }
+}
+
+async fn async_drop(value: impl AsyncDrop + Send + std::marker::Sync + 'static) {
+    let (value, last_value) = Arcy::new(value);
+    drop(value);
+    assert!(last_value.await.is_completed());
+}
+
+#[derive(AsyncDrop)]
+struct Sequential {
+    a: Conn,
+    b: Conn,
+}
+
+#[tokio::test]
+async fn fields_in_declaration_order() {
+    let log = Log::default();
+    async_drop(Sequential {
+        a: Conn("a", log.clone()),
+        b: Conn("b", log.clone()),
+    })
+    .await;
+    assert_eq!(log.take(), ["start a", "end a", "start b", "end b"]);
+}
+
+#[derive(AsyncDrop)]
+struct Ordered {
+    #[async_drop(order = 1)]
+    a: Conn,
+    b: Conn,
+    #[async_drop(order = -1)]
+    c: Conn,
+}
+
+#[tokio::test]
+async fn fields_by_order() {
+    let log = Log::default();
+    async_drop(Ordered {
+        a: Conn("a", log.clone()),
+        b: Conn("b", log.clone()),
+        c: Conn("c", log.clone()),
+    })
+    .await;
+    let expected = ["start c", "end c", "start b", "end b", "start a", "end a"];
+    assert_eq!(log.take(), expected);
+}
+
+#[derive(AsyncDrop)]
+struct Joined {
+    #[async_drop(join)]
+    a: Conn,
+    b: Conn,
+    #[async_drop(join)]
+    c: Conn,
+    #[async_drop(order = 1)]
+    d: Conn,
+}
+
+#[tokio::test]
+async fn joined_fields_concurrently() {
+    let log = Log::default();
+    async_drop(Joined {
+        a: Conn("a", log.clone()),
+        b: Conn("b", log.clone()),
+        c: Conn("c", log.clone()),
+        d: Conn("d", log.clone()),
+    })
+    .await;
+    let expected = [
+        "start a", "start c", "end a", "end c", "start b", "end b", "start d", "end d",
+    ];
+    assert_eq!(log.take(), expected);
+}
+
+#[derive(AsyncDrop)]
+#[async_drop(join)]
+struct AllJoined(Conn, Conn);
+
+#[tokio::test]
+async fn all_fields_joined() {
+    let log = Log::default();
+    async_drop(AllJoined(Conn("a", log.clone()), Conn("b", log.clone()))).await;
+    assert_eq!(log.take(), ["start a", "start b", "end a", "end b"]);
+}
+
+#[derive(AsyncDrop)]
+struct Skipped {
+    #[async_drop(skip)]
+    a: Sync,
+    b: Conn,
+}
+
+#[tokio::test]
+async fn skipped_fields_last() {
+    let log = Log::default();
+    async_drop(Skipped {
+        a: Sync("a", log.clone()),
+        b: Conn("b", log.clone()),
+    })
+    .await;
+    assert_eq!(log.take(), ["start b", "end b", "drop a"]);
+}
+
+#[derive(AsyncDrop)]
+enum Either {
+    Left(Conn),
+    Right {
+        #[async_drop(skip)]
+        first: Sync,
+        second: Conn,
+    },
+    Neither,
+}
+
+#[tokio::test]
+async fn enum_variants() {
+    let log = Log::default();
+    async_drop(Either::Left(Conn("left", log.clone()))).await;
+    async_drop(Either::Right {
+        first: Sync("first", log.clone()),
+        second: Conn("second", log.clone()),
+    })
+    .await;
+    async_drop(Either::Neither).await;
+    let expected = [
+        "start left",
+        "end left",
+        "start second",
+        "end second",
+        "drop first",
+    ];
+    assert_eq!(log.take(), expected);
+}
+
+#[derive(AsyncDrop)]
+struct Underscored {
+    _conn: Conn,
+    #[async_drop(skip)]
+    _name: String,
+}
+
+#[tokio::test]
+async fn underscored_fields() {
+    let log = Log::default();
+    async_drop(Underscored {
+        _conn: Conn("conn", log.clone()),
+        _name: "name".to_owned(),
+    })
+    .await;
+    assert_eq!(log.take(), ["start conn", "end conn"]);
+}
+
+#[derive(AsyncDrop)]
+struct Borrowing<'a> {
+    conn: Conn,
+    #[async_drop(skip)]
+    name: &'a str,
+}
+
+#[tokio::test]
+async fn lifetime_parameters() {
+    let log = Log::default();
+    let value = Borrowing {
+        conn: Conn("conn", log.clone()),
+        name: "name",
+    };
+    assert_eq!(value.name, "name");
+    // `Arcy` needs `'static`, the impl itself doesn't
+    value.async_drop().await;
+    assert_eq!(log.take(), ["start conn", "end conn"]);
+}
+
+#[derive(AsyncDrop)]
+struct Generic<T> {
+    inner: T,
+}
+
+#[tokio::test]
+async fn type_parameters() {
+    let log = Log::default();
+    async_drop(Generic {
+        inner: Conn("inner", log.clone()),
+    })
+    .await;
+    assert_eq!(log.take(), ["start inner", "end inner"]);
+}
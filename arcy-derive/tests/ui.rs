@@ -0,0 +1,6 @@
+//! Invalid uses of `#[derive(AsyncDrop)]`.
+
+#[test]
+fn ui() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
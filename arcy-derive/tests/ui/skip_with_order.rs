@@ -0,0 +1,9 @@
+use arcy::{AsyncDrop, SyncDrop};
+
+#[derive(AsyncDrop)]
+struct Conn {
+    #[async_drop(skip, order = 1)]
+    fd: SyncDrop<u32>,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use arcy::AsyncDrop;
+
+#[derive(AsyncDrop)]
+union Conn {
+    fd: u32,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use arcy::{AsyncDrop, SyncDrop};
+
+#[derive(AsyncDrop)]
+#[async_drop(skip)]
+struct Conn {
+    fd: SyncDrop<u32>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use arcy::{AsyncDrop, SyncDrop};
+
+#[derive(AsyncDrop)]
+struct Conn {
+    #[async_drop(last)]
+    fd: SyncDrop<u32>,
+}
+
+fn main() {}
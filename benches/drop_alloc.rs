@@ -0,0 +1,106 @@
+//! Compares the cost of an async drop implemented with a native `async fn` with
+//! one implemented with `async_trait`, which boxes the future it returns.
+//!
+//! Besides timing them, the allocations made while the last reference is dropped
+//! and the drop task runs are counted and printed.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+use arcy::compat::{self, Compat};
+use arcy::{Arcy, AsyncDrop, DropHandle};
+use criterion::{criterion_group, criterion_main, Criterion};
+use tokio::runtime::{Builder, Runtime};
+
+const N: usize = 1_000;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+struct Native;
+
+impl AsyncDrop for Native {
+    async fn async_drop(self) {
+        tokio::task::yield_now().await;
+    }
+}
+
+struct Boxed;
+
+#[async_trait::async_trait]
+impl compat::AsyncDrop for Boxed {
+    async fn async_drop(self) {
+        tokio::task::yield_now().await;
+    }
+}
+
+fn runtime() -> Runtime {
+    Builder::new_current_thread().enable_all().build().unwrap()
+}
+
+/// Drops all the values and waits for their async drops.
+async fn drop_all<T>(live: Vec<Arcy<T>>, handles: Vec<DropHandle>) {
+    drop(live);
+    for handle in handles {
+        assert!(handle.await.is_completed());
+    }
+}
+
+/// Returns the number of allocations made by the async drop of a value.
+fn allocations_per_drop<T>(rt: &Runtime, value: impl Fn() -> T) -> f64
+where
+    T: AsyncDrop + Send + Sync + 'static,
+{
+    rt.block_on(async {
+        let (live, handles) = (0..N).map(|_| Arcy::new(value())).unzip();
+        let before = ALLOCATIONS.load(Relaxed);
+        drop_all(live, handles).await;
+        (ALLOCATIONS.load(Relaxed) - before) as f64 / N as f64
+    })
+}
+
+fn create_and_drop(c: &mut Criterion) {
+    let rt = runtime();
+    println!(
+        "allocations per async drop: native {}, boxed {}",
+        allocations_per_drop(&rt, || Native),
+        allocations_per_drop(&rt, || Compat(Boxed)),
+    );
+
+    let mut group = c.benchmark_group("drop_alloc");
+    group.bench_function("native", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let (live, handles) = (0..N).map(|_| Arcy::new(Native)).unzip();
+                drop_all(live, handles).await;
+            })
+        })
+    });
+    group.bench_function("boxed", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let (live, handles) = (0..N).map(|_| Arcy::new(Compat(Boxed))).unzip();
+                drop_all(live, handles).await;
+            })
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, create_and_drop);
+criterion_main!(benches);
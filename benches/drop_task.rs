@@ -0,0 +1,114 @@
+//! Compares spawning the async drop lazily, once the last reference is gone,
+//! with the previous model of parking one slayer task per allocation.
+
+use std::sync::Arc;
+
+use arcy::{Arcy, AsyncDrop};
+use criterion::{criterion_group, criterion_main, Criterion};
+use tokio::runtime::Runtime;
+use tokio::sync::{oneshot, Notify};
+use tokio::task::JoinHandle;
+
+const N: usize = 1_000;
+
+struct Conn;
+
+impl AsyncDrop for Conn {
+    async fn async_drop(self) {
+        tokio::task::yield_now().await;
+    }
+}
+
+/// The eager model: a task is spawned at construction and parked on a `Notify`
+/// until the value is handed over.
+struct Eager {
+    value: Option<Conn>,
+    notify: Arc<Notify>,
+    slayer: Option<oneshot::Sender<Conn>>,
+}
+
+impl Eager {
+    fn new(value: Conn) -> (Self, JoinHandle<()>) {
+        let notify = Arc::new(Notify::new());
+        let (tx, rx) = oneshot::channel::<Conn>();
+        let parked = Arc::clone(&notify);
+        let slayer = tokio::spawn(async move {
+            parked.notified().await;
+            if let Ok(value) = rx.await {
+                value.async_drop().await;
+            }
+        });
+        let eager = Self {
+            value: Some(value),
+            notify,
+            slayer: Some(tx),
+        };
+        (eager, slayer)
+    }
+}
+
+impl Drop for Eager {
+    fn drop(&mut self) {
+        if let (Some(value), Some(slayer)) = (self.value.take(), self.slayer.take()) {
+            let _ = slayer.send(value);
+        }
+        self.notify.notify_one();
+    }
+}
+
+fn create(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("create");
+
+    group.bench_function("lazy", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let live: Vec<_> = (0..N).map(|_| Arcy::new(Conn)).collect();
+                live
+            })
+        })
+    });
+    group.bench_function("eager", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let live: Vec<_> = (0..N).map(|_| Eager::new(Conn)).collect();
+                live
+            })
+        })
+    });
+
+    group.finish();
+}
+
+fn create_and_drop(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("create_and_drop");
+
+    group.bench_function("lazy", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let (live, handles): (Vec<_>, Vec<_>) = (0..N).map(|_| Arcy::new(Conn)).unzip();
+                drop(live);
+                for handle in handles {
+                    assert!(handle.await.is_completed());
+                }
+            })
+        })
+    });
+    group.bench_function("eager", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let (live, handles): (Vec<_>, Vec<_>) = (0..N).map(|_| Eager::new(Conn)).unzip();
+                drop(live);
+                for handle in handles {
+                    handle.await.unwrap();
+                }
+            })
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, create, create_and_drop);
+criterion_main!(benches);
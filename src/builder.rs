@@ -0,0 +1,302 @@
+use std::any::Any;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::fallback::SyncFallback;
+use crate::inner::ArcyInner;
+use crate::release::{DropConfig, DropOptions, Executor, Header};
+use crate::{
+    Arcy, DropExecutor, DropHandle, DropLimiter, DropStrategy, DropTracker, PanicPolicy,
+    TryAsyncDrop, TryNewError,
+};
+
+/// Configures how an [`Arcy`] is async dropped.
+///
+/// Created with [`Arcy::builder`].
+///
+/// # Examples
+///
+#[cfg_attr(feature = "tokio", doc = "```")]
+#[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+/// # use arcy::{Arcy, AsyncDrop, DropTracker};
+/// # struct Foo;
+/// # impl AsyncDrop for Foo {
+/// #     async fn async_drop(self) {}
+/// # }
+/// # #[tokio::main]
+/// # async fn main() {
+/// let tracker = DropTracker::new();
+/// let (foo, last_foo) = Arcy::builder()
+///     .executor(tokio::runtime::Handle::current())
+///     .tracker(&tracker)
+///     .build(Foo);
+/// # }
+/// ```
+pub struct ArcyBuilder<T> {
+    executor: Option<Executor>,
+    name: Option<Arc<str>>,
+    strategy: DropStrategy,
+    panic_policy: PanicPolicy,
+    timeout: Option<Duration>,
+    on_timeout: Option<Box<dyn FnOnce() + Send>>,
+    tracker: Option<DropTracker>,
+    limiter: Option<DropLimiter>,
+    fallback: Option<Arc<dyn Any + Send + Sync>>,
+    _value: PhantomData<fn(T)>,
+}
+
+impl<T> ArcyBuilder<T>
+where
+    T: TryAsyncDrop + Send + Sync + 'static,
+{
+    pub(crate) fn new() -> Self {
+        Self {
+            executor: None,
+            name: None,
+            strategy: DropStrategy::default(),
+            panic_policy: PanicPolicy::default(),
+            timeout: None,
+            on_timeout: None,
+            tracker: None,
+            limiter: None,
+            fallback: None,
+            _value: PhantomData,
+        }
+    }
+
+    /// Sets the executor the async drop is spawned on.
+    ///
+    /// Defaults to the Tokio runtime [`build`][Self::build] is called from: its
+    /// handle is captured then, so the async drop lands on that runtime whichever
+    /// thread drops the last `Arcy`. Pass a
+    #[cfg_attr(feature = "tokio", doc = "[`Handle`][tokio::runtime::Handle]")]
+    #[cfg_attr(not(feature = "tokio"), doc = "`tokio::runtime::Handle`")]
+    /// to pick another runtime, or
+    #[cfg_attr(feature = "tokio", doc = "[`executor::Tokio`][crate::executor::Tokio]")]
+    #[cfg_attr(not(feature = "tokio"), doc = "`executor::Tokio`")]
+    /// to use the runtime current when the last `Arcy` is dropped.
+    pub fn executor(mut self, executor: impl DropExecutor) -> Self {
+        self.executor = Some(Executor::Custom(Arc::new(executor)));
+        self
+    }
+
+    /// Names the async drop after the value.
+    ///
+    /// The name is passed to [`DropExecutor::spawn_named`] and shows up in the
+    /// message logged by [`PanicPolicy::Log`].
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into().into());
+        self
+    }
+
+    /// Sets where the async drop starts running.
+    ///
+    /// Defaults to [`DropStrategy::Spawn`].
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// use arcy::{Arcy, AsyncDrop, DropStrategy};
+    ///
+    /// struct Flag(Arc<AtomicBool>);
+    ///
+    /// impl AsyncDrop for Flag {
+    ///     async fn async_drop(self) {
+    ///         self.0.store(true, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let dropped = Arc::new(AtomicBool::new(false));
+    /// let (flag, _) = Arcy::builder()
+    ///     .strategy(DropStrategy::Inline)
+    ///     .build(Flag(Arc::clone(&dropped)));
+    /// drop(flag);
+    /// // dropped right away, without spawning a task
+    /// assert!(dropped.load(Ordering::SeqCst));
+    /// # }
+    /// ```
+    pub fn strategy(mut self, strategy: DropStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Sets what happens if [`TryAsyncDrop::try_async_drop`] panics.
+    ///
+    /// Defaults to [`PanicPolicy::Log`].
+    pub fn panic_policy(mut self, panic_policy: PanicPolicy) -> Self {
+        self.panic_policy = panic_policy;
+        self
+    }
+
+    /// Abandons the async drop if it doesn't complete within `timeout`.
+    ///
+    /// The drop future is cancelled and the [`DropHandle`] resolves to
+    /// [`DropOutcome::TimedOut`][crate::DropOutcome::TimedOut]. The timer is
+    /// provided by the executor, see [`DropExecutor::sleep`]: executors without a
+    /// timer of their own, such as `ThreadPool`, share a single timer thread.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// # use std::time::Duration;
+    /// # use arcy::{Arcy, AsyncDrop};
+    /// struct Hung;
+    ///
+    /// impl AsyncDrop for Hung {
+    ///     async fn async_drop(self) {
+    ///         futures::future::pending::<()>().await;
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (hung, last_hung) = Arcy::builder()
+    ///     .drop_timeout(Duration::from_millis(10))
+    ///     .on_timeout(|| eprintln!("gave up closing"))
+    ///     .build(Hung);
+    /// drop(hung);
+    /// assert!(last_hung.await.is_timed_out());
+    /// # }
+    /// ```
+    pub fn drop_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Calls `f` if the async drop is abandoned because of the
+    /// [`drop_timeout`][Self::drop_timeout].
+    pub fn on_timeout(mut self, f: impl FnOnce() + Send + 'static) -> Self {
+        self.on_timeout = Some(Box::new(f));
+        self
+    }
+
+    /// Counts the value as pending in `tracker` until its async drop completes.
+    pub fn tracker(mut self, tracker: &DropTracker) -> Self {
+        self.tracker = Some(tracker.clone());
+        self
+    }
+
+    /// Makes the async drop wait for a permit of `limiter` before it starts.
+    ///
+    /// Defaults to the [global limiter][DropLimiter::set_global], if any.
+    pub fn limiter(mut self, limiter: &DropLimiter) -> Self {
+        self.limiter = Some(limiter.clone());
+        self
+    }
+
+    /// Calls `f` with the value if its async drop never starts.
+    ///
+    /// That's the case if the executor can't spawn it, or drops it before it
+    /// runs, e.g. because the runtime has shut down by the time the last `Arcy` is
+    /// dropped. `f` then runs on the thread dropping the async drop, and the
+    /// [`DropHandle`] still resolves to
+    /// [`DropOutcome::Cancelled`][crate::DropOutcome::Cancelled]. An async drop
+    /// cancelled once started can't fall back: the value was moved into it.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    ///
+    /// use arcy::{Arcy, AsyncDrop};
+    /// use tokio::runtime::Runtime;
+    ///
+    /// static CLOSED: AtomicBool = AtomicBool::new(false);
+    ///
+    /// struct Conn;
+    ///
+    /// impl AsyncDrop for Conn {
+    ///     async fn async_drop(self) {
+    ///         CLOSED.store(true, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let runtime = Runtime::new().unwrap();
+    /// let (conn, _) = Arcy::builder()
+    ///     .executor(runtime.handle().clone())
+    ///     .sync_fallback(|_: Conn| CLOSED.store(true, Ordering::SeqCst))
+    ///     .build(Conn);
+    /// drop(runtime);
+    /// drop(conn);
+    /// assert!(CLOSED.load(Ordering::SeqCst));
+    /// ```
+    pub fn sync_fallback(mut self, f: impl Fn(T) + Send + Sync + 'static) -> Self {
+        self.fallback = Some(SyncFallback::erased(f));
+        self
+    }
+
+    /// Constructs the `Arcy<T>`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no executor was set and there is no current Tokio runtime,
+    /// see [`try_build`][Self::try_build].
+    pub fn build(self, value: T) -> (Arcy<T>, DropHandle<T::Error>) {
+        self.try_build(value)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Constructs the `Arcy<T>`, failing if no executor was set and there is
+    /// no current Tokio runtime.
+    #[allow(clippy::type_complexity)]
+    pub fn try_build(self, value: T) -> Result<(Arcy<T>, DropHandle<T::Error>), TryNewError<T>> {
+        let executor = match self.executor {
+            Some(executor) => executor,
+            None => match default_executor() {
+                Some(executor) => executor,
+                None => return Err(TryNewError::new(value)),
+            },
+        };
+        let options = DropOptions {
+            name: self.name,
+            timeout: self.timeout,
+            panic_policy: self.panic_policy,
+            tracker: self.tracker,
+            limiter: self.limiter.or_else(DropLimiter::global),
+            fallback: self.fallback,
+        };
+        let config = DropConfig {
+            executor,
+            strategy: self.strategy,
+            options: (!options.is_default()).then(|| Arc::new(options)),
+        };
+        let (header, handle) = Header::new::<T>(config, self.on_timeout);
+        let inner = Arc::new(ArcyInner::new(value, header));
+        Ok((Arcy { inner }, handle))
+    }
+}
+
+#[cfg(feature = "tokio")]
+fn default_executor() -> Option<Executor> {
+    let runtime = tokio::runtime::Handle::try_current().ok()?;
+    Some(Executor::Runtime(runtime))
+}
+
+#[cfg(not(feature = "tokio"))]
+fn default_executor() -> Option<Executor> {
+    None
+}
+
+impl<T> fmt::Debug for ArcyBuilder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArcyBuilder")
+            .field("name", &self.name)
+            .field("strategy", &self.strategy)
+            .field("panic_policy", &self.panic_policy)
+            .field("timeout", &self.timeout)
+            .field("tracker", &self.tracker)
+            .field("limiter", &self.limiter)
+            .field("sync_fallback", &self.fallback.is_some())
+            .finish_non_exhaustive()
+    }
+}
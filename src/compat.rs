@@ -0,0 +1,74 @@
+//! Drop traits in the [`async_trait`] style, for impls written before `async fn`
+//! in traits was available.
+//!
+//! Requires the `async-trait` feature. Every future returned by these traits is
+//! boxed, prefer implementing [`crate::AsyncDrop`] directly.
+//!
+//! Values implementing them are put into an [`Arcy`][crate::Arcy] through the
+//! [`Compat`] wrapper: a blanket impl would conflict with the impl of
+//! [`crate::AsyncDrop`] for `Box<T>`.
+//!
+//! [`async_trait`]: https://docs.rs/async-trait
+
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+
+use async_trait::async_trait;
+
+/// Boxed version of [`crate::AsyncDrop`].
+///
+/// Wrapped in [`Compat`], every type implementing it is a [`crate::AsyncDrop`] too.
+///
+/// # Examples
+///
+/// ```
+/// use arcy::compat::{AsyncDrop, Compat};
+/// use arcy::Arcy;
+///
+/// struct Foo;
+///
+/// #[async_trait::async_trait]
+/// impl AsyncDrop for Foo {
+///     async fn async_drop(self) {
+///         // do something asynchronously
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (foo, last_foo) = Arcy::new(Compat(Foo));
+/// drop(foo);
+/// assert!(last_foo.await.is_completed());
+/// # }
+/// ```
+#[async_trait]
+pub trait AsyncDrop {
+    async fn async_drop(self);
+}
+
+/// Adapts a [`compat::AsyncDrop`][AsyncDrop] into a [`crate::AsyncDrop`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Compat<T>(pub T);
+
+impl<T> crate::AsyncDrop for Compat<T>
+where
+    T: AsyncDrop + Send + 'static,
+{
+    fn async_drop(self) -> impl Future<Output = ()> + Send {
+        AsyncDrop::async_drop(self.0)
+    }
+}
+
+impl<T> Deref for Compat<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Compat<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
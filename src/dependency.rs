@@ -0,0 +1,115 @@
+//! Async drops waiting for the async drops of other values.
+
+use std::ptr;
+
+use parking_lot::{const_mutex, Mutex};
+
+use crate::release::Header;
+use crate::{Arcy, CycleError};
+
+/// A strong reference to a value another value depends on.
+pub(crate) trait Dependency: Send + Sync {
+    fn header(&self) -> &Header;
+
+    /// Another strong reference to the same value.
+    fn clone_box(&self) -> Box<dyn Dependency>;
+}
+
+impl<T> Dependency for Arcy<T>
+where
+    T: ?Sized + Send + Sync + 'static,
+{
+    fn header(&self) -> &Header {
+        &self.inner.header
+    }
+
+    fn clone_box(&self) -> Box<dyn Dependency> {
+        Box::new(self.clone())
+    }
+}
+
+/// Serializes the declarations, so that two of them can't race to close a cycle.
+static DECLARATIONS: Mutex<()> = const_mutex(());
+
+impl<T: ?Sized> Arcy<T> {
+    /// Declares that the async drop of `this` must complete before the one of
+    /// `dependency` starts.
+    ///
+    /// `this` keeps a strong reference to `dependency` until its own async drop
+    /// is over, so the dependency is not released before that even if all its
+    /// other `Arcy`s are gone. Fails if `dependency` already depends on `this`,
+    /// as neither could ever be released; declaring the same dependency again
+    /// does nothing.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// use arcy::{Arcy, AsyncDrop};
+    ///
+    /// struct Pool;
+    ///
+    /// impl AsyncDrop for Pool {
+    ///     async fn async_drop(self) {
+    ///         println!("closing the pool");
+    ///     }
+    /// }
+    ///
+    /// struct Session;
+    ///
+    /// impl AsyncDrop for Session {
+    ///     async fn async_drop(self) {
+    ///         println!("closing the session");
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (pool, last_pool) = Arcy::new(Pool);
+    /// let (session, last_session) = Arcy::new(Session);
+    /// Arcy::depends_on(&session, &pool).unwrap();
+    /// assert!(Arcy::depends_on(&pool, &session).is_err());
+    ///
+    /// let weak_pool = Arcy::downgrade(&pool);
+    /// drop(pool);
+    /// // the session still holds the pool
+    /// assert!(weak_pool.upgrade().is_some());
+    ///
+    /// drop(session);
+    /// assert!(last_session.await.is_completed());
+    /// assert!(last_pool.await.is_completed());
+    /// # }
+    /// ```
+    pub fn depends_on<U>(this: &Self, dependency: &Arcy<U>) -> Result<(), CycleError>
+    where
+        U: ?Sized + Send + Sync + 'static,
+    {
+        let _declaring = DECLARATIONS.lock();
+        if reaches(&dependency.inner.header, &this.inner.header) {
+            return Err(CycleError(()));
+        }
+        if let Some(mut dependencies) = this.inner.header.dependencies() {
+            let header = &dependency.inner.header;
+            if !dependencies
+                .iter()
+                .any(|known| ptr::eq(known.header(), header))
+            {
+                dependencies.push(Box::new(dependency.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether `to` is `from` or one of its transitive dependencies.
+fn reaches(from: &Header, to: &Header) -> bool {
+    if ptr::eq(from, to) {
+        return true;
+    }
+    // the graph has no cycles, so no header is locked twice
+    from.dependencies().is_some_and(|dependencies| {
+        dependencies
+            .iter()
+            .any(|dependency| reaches(dependency.header(), to))
+    })
+}
@@ -0,0 +1,63 @@
+use std::fmt;
+
+/// Error returned by
+#[cfg_attr(feature = "tokio", doc = "[`Arcy::try_new`][crate::Arcy::try_new]")]
+#[cfg_attr(not(feature = "tokio"), doc = "`Arcy::try_new`")]
+/// and [`ArcyBuilder::try_build`][crate::ArcyBuilder::try_build] when there is no
+/// executor to spawn the async drop on: none was configured and the call was
+/// made outside the context of a Tokio runtime.
+///
+/// The value that could not be wrapped is handed back and can be recovered
+/// with [`into_inner`][Self::into_inner].
+pub struct TryNewError<T>(T);
+
+impl<T> TryNewError<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Returns the value that could not be wrapped.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Debug for TryNewError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TryNewError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for TryNewError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "there is no executor to spawn the async drop on")
+    }
+}
+
+impl<T> std::error::Error for TryNewError<T> {}
+
+/// Error returned by [`DropTracker::drain_timeout`][crate::DropTracker::drain_timeout]
+/// when some async drops are still pending after the deadline.
+#[derive(Debug, Clone, Copy)]
+pub struct Elapsed(pub(crate) ());
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "deadline elapsed with async drops still pending")
+    }
+}
+
+impl std::error::Error for Elapsed {}
+
+/// Error returned by [`Arcy::depends_on`][crate::Arcy::depends_on] when the
+/// dependency already depends, directly or not, on the dependent.
+#[derive(Debug, Clone, Copy)]
+pub struct CycleError(pub(crate) ());
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the async drop dependency would create a cycle")
+    }
+}
+
+impl std::error::Error for CycleError {}
@@ -0,0 +1,230 @@
+//! Executors the async drops can be spawned on.
+//!
+//! An [`Arcy`][crate::Arcy] spawns [`AsyncDrop::async_drop`][crate::AsyncDrop::async_drop]
+//! on the [`DropExecutor`] it was created with, see [`Arcy::new_in`][crate::Arcy::new_in].
+//! Implementations are provided for:
+//!
+//! * `tokio::runtime::Handle` and `Tokio`, with the `tokio` feature (enabled by default),
+//! * `futures::executor::ThreadPool`, with the `thread-pool` feature.
+
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+
+use crate::timer;
+
+/// Spawns the future running the async drop of a value.
+///
+/// # Examples
+///
+/// An executor that runs every drop to completion on a thread of its own:
+///
+/// ```
+/// use arcy::{Arcy, AsyncDrop, DropExecutor};
+/// use futures::future::BoxFuture;
+///
+/// struct ThreadPerDrop;
+///
+/// impl DropExecutor for ThreadPerDrop {
+///     fn spawn(&self, future: BoxFuture<'static, ()>) -> Result<(), BoxFuture<'static, ()>> {
+///         std::thread::spawn(move || futures::executor::block_on(future));
+///         Ok(())
+///     }
+/// }
+///
+/// struct Foo;
+///
+/// impl AsyncDrop for Foo {
+///     async fn async_drop(self) {}
+/// }
+///
+/// let (foo, last_foo) = Arcy::new_in(ThreadPerDrop, Foo);
+/// drop(foo);
+/// assert!(futures::executor::block_on(last_foo).is_completed());
+/// ```
+pub trait DropExecutor: Send + Sync + 'static {
+    /// Spawns `future`, which must eventually be polled to completion.
+    ///
+    /// If the future can't be run it is handed back; dropping it drops the
+    /// value synchronously.
+    fn spawn(&self, future: BoxFuture<'static, ()>) -> Result<(), BoxFuture<'static, ()>>;
+
+    /// Like [`spawn`][Self::spawn], for a drop [named][crate::ArcyBuilder::name]
+    /// after the value.
+    ///
+    /// The default implementation ignores the name. With the `tracing` feature
+    /// and `--cfg tokio_unstable`, Tokio tasks get the name, e.g. for
+    /// `tokio-console`.
+    fn spawn_named(
+        &self,
+        name: &str,
+        future: BoxFuture<'static, ()>,
+    ) -> Result<(), BoxFuture<'static, ()>> {
+        let _ = name;
+        self.spawn(future)
+    }
+
+    /// Runs `future` to completion on the current thread, for the
+    /// [`DropStrategy::BlockOn`][crate::DropStrategy::BlockOn] drops.
+    ///
+    /// If the current thread must not block, e.g. because it runs an executor
+    /// itself, the future is handed back to be [spawned][Self::spawn] instead.
+    /// The default implementation blocks with `futures::executor::block_on`,
+    /// unless the current thread runs a `futures` executor, e.g. a `ThreadPool`.
+    fn block_on(&self, future: BoxFuture<'static, ()>) -> Result<(), BoxFuture<'static, ()>> {
+        block_on_unless_entered(future)
+    }
+
+    /// Returns a future that completes after `duration`, used to enforce
+    /// [drop timeouts][crate::ArcyBuilder::drop_timeout].
+    ///
+    /// The returned future is polled from within futures spawned on this executor.
+    /// The default implementation uses a timer thread shared by every
+    /// executor, started on first use; executors that come with a timer should
+    /// override it.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(timer::sleep(duration))
+    }
+}
+
+/// Blocks on `future` with `futures::executor::block_on`, unless the current
+/// thread already runs a `futures` executor, which would make it panic.
+fn block_on_unless_entered(future: BoxFuture<'static, ()>) -> Result<(), BoxFuture<'static, ()>> {
+    // the guard is dropped right away: `block_on` enters again
+    if futures::executor::enter().is_err() {
+        return Err(future);
+    }
+    futures::executor::block_on(future);
+    Ok(())
+}
+
+impl<E> DropExecutor for std::sync::Arc<E>
+where
+    E: DropExecutor + ?Sized,
+{
+    fn spawn(&self, future: BoxFuture<'static, ()>) -> Result<(), BoxFuture<'static, ()>> {
+        (**self).spawn(future)
+    }
+
+    fn spawn_named(
+        &self,
+        name: &str,
+        future: BoxFuture<'static, ()>,
+    ) -> Result<(), BoxFuture<'static, ()>> {
+        (**self).spawn_named(name, future)
+    }
+
+    fn block_on(&self, future: BoxFuture<'static, ()>) -> Result<(), BoxFuture<'static, ()>> {
+        (**self).block_on(future)
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        (**self).sleep(duration)
+    }
+}
+
+/// Spawns on a specific Tokio runtime.
+///
+/// Once the runtime shuts down, spawned futures are dropped without being run.
+#[cfg(feature = "tokio")]
+impl DropExecutor for tokio::runtime::Handle {
+    fn spawn(&self, future: BoxFuture<'static, ()>) -> Result<(), BoxFuture<'static, ()>> {
+        // resolves to the inherent method
+        Self::spawn(self, future);
+        Ok(())
+    }
+
+    #[cfg(all(tokio_unstable, feature = "tracing"))]
+    fn spawn_named(
+        &self,
+        name: &str,
+        future: BoxFuture<'static, ()>,
+    ) -> Result<(), BoxFuture<'static, ()>> {
+        let spawned = tokio::task::Builder::new()
+            .name(name)
+            .spawn_on(future, self);
+        spawned.expect("spawning a task on a runtime can't fail");
+        Ok(())
+    }
+
+    /// Blocks on the runtime unless the current thread runs a Tokio runtime or a
+    /// `futures` executor.
+    fn block_on(&self, future: BoxFuture<'static, ()>) -> Result<(), BoxFuture<'static, ()>> {
+        if Self::try_current().is_ok() || futures::executor::enter().is_err() {
+            return Err(future);
+        }
+        // resolves to the inherent method
+        Self::block_on(self, future);
+        Ok(())
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let runtime = self.clone();
+        Box::pin(async move {
+            // the timer of the runtime is looked up when the sleep is created
+            let sleep = {
+                let _entered = runtime.enter();
+                tokio::time::sleep(duration)
+            };
+            sleep.await
+        })
+    }
+}
+
+/// Spawns on whatever Tokio runtime is current when the last [`Arcy`][crate::Arcy]
+/// is dropped.
+///
+/// Unlike a [`Handle`][tokio::runtime::Handle], this executor can be created
+/// outside of a runtime, but the last reference must be released within one.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tokio;
+
+#[cfg(feature = "tokio")]
+impl DropExecutor for Tokio {
+    fn spawn(&self, future: BoxFuture<'static, ()>) -> Result<(), BoxFuture<'static, ()>> {
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => runtime.spawn(future),
+            Err(_) => return Err(future),
+        };
+        Ok(())
+    }
+
+    #[cfg(all(tokio_unstable, feature = "tracing"))]
+    fn spawn_named(
+        &self,
+        name: &str,
+        future: BoxFuture<'static, ()>,
+    ) -> Result<(), BoxFuture<'static, ()>> {
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => runtime.spawn_named(name, future),
+            Err(_) => Err(future),
+        }
+    }
+
+    /// Blocks unless the current thread runs a Tokio runtime or a `futures`
+    /// executor. There is no runtime to block on then: the future can't rely on
+    /// Tokio's timers or IO.
+    fn block_on(&self, future: BoxFuture<'static, ()>) -> Result<(), BoxFuture<'static, ()>> {
+        if tokio::runtime::Handle::try_current().is_ok() {
+            return Err(future);
+        }
+        block_on_unless_entered(future)
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => DropExecutor::sleep(&runtime, duration),
+            // the drop is polled outside of a runtime, see `DropStrategy`
+            Err(_) => Box::pin(timer::sleep(duration)),
+        }
+    }
+}
+
+#[cfg(feature = "thread-pool")]
+impl DropExecutor for futures::executor::ThreadPool {
+    fn spawn(&self, future: BoxFuture<'static, ()>) -> Result<(), BoxFuture<'static, ()>> {
+        self.spawn_ok(future);
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+//! Synchronous cleanup for the values whose async drop never got to start.
+
+use std::any::Any;
+use std::sync::Arc;
+
+/// A [`sync_fallback`][crate::ArcyBuilder::sync_fallback] for values of type `T`,
+/// downcast back by [`Unstarted`].
+pub(crate) struct SyncFallback<T>(Box<dyn Fn(T) + Send + Sync>);
+
+impl<T> SyncFallback<T>
+where
+    T: 'static,
+{
+    /// Type erases `f`, to be stored in the `DropConfig`.
+    pub(crate) fn erased(f: impl Fn(T) + Send + Sync + 'static) -> Arc<dyn Any + Send + Sync> {
+        Arc::new(Self(Box::new(f)))
+    }
+}
+
+/// A value whose async drop hasn't started yet.
+///
+/// Dropping it hands the value to the fallback, if any.
+pub(crate) struct Unstarted<T>
+where
+    T: 'static,
+{
+    value: Option<T>,
+    fallback: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl<T> Unstarted<T>
+where
+    T: 'static,
+{
+    pub(crate) fn new(value: T, fallback: Option<Arc<dyn Any + Send + Sync>>) -> Self {
+        Self {
+            value: Some(value),
+            fallback,
+        }
+    }
+
+    /// Takes the value out to async drop it.
+    pub(crate) fn start(mut self) -> T {
+        self.value.take().unwrap()
+    }
+}
+
+impl<T> Drop for Unstarted<T>
+where
+    T: 'static,
+{
+    fn drop(&mut self) {
+        let value = match self.value.take() {
+            Some(value) => value,
+            None => return,
+        };
+        // created for this very `T` by the builder
+        let fallback = self.fallback.as_deref();
+        match fallback.and_then(|fallback| fallback.downcast_ref::<SyncFallback<T>>()) {
+            Some(SyncFallback(f)) => f(value),
+            None => drop(value),
+        }
+    }
+}
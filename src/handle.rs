@@ -0,0 +1,296 @@
+use std::any::Any;
+use std::convert::Infallible;
+use std::fmt;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering::{AcqRel, Acquire};
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+use parking_lot::Mutex;
+
+use crate::DropOutcome;
+
+/// The error of a failed async drop, before being downcast back to the
+/// [`TryAsyncDrop::Error`][crate::TryAsyncDrop::Error] of the value.
+pub(crate) type AnyError = Box<dyn Any + Send>;
+
+/// What the async drop of a value shares with whoever waits for it: its
+/// [`DropHandle`] and [`DropFuture`]s.
+///
+/// It's the only allocation an `Arcy` needs besides its own until the async
+/// drop is spawned, so it's kept small, and is checked without locking: only
+/// handing over the outcome and waiting lock.
+#[derive(Default)]
+pub(crate) struct DropState {
+    flags: AtomicU8,
+    shared: Mutex<Shared>,
+}
+
+#[derive(Default)]
+struct Shared {
+    // sent once reported, until the handle takes it
+    outcome: Option<DropOutcome<AnyError>>,
+    handle: Option<Waker>,
+    waiters: Vec<Waker>,
+}
+
+// the async drop is over or won't happen
+const FINISHED: u8 = 1;
+const ABORTED: u8 = 1 << 1;
+const HAS_HANDLE: u8 = 1 << 2;
+// the handle has its outcome: once finished, or as soon as aborted
+const REPORTED: u8 = 1 << 3;
+
+/// Resolves once the last [`Arcy`][crate::Arcy] to a value has been dropped and
+/// the async drop is over, telling how it went.
+///
+/// Returned by
+#[cfg_attr(feature = "tokio", doc = "[`Arcy::new`][crate::Arcy::new],")]
+#[cfg_attr(not(feature = "tokio"), doc = "`Arcy::new`,")]
+/// [`Arcy::new_in`][crate::Arcy::new_in] or [`ArcyBuilder`][crate::ArcyBuilder],
+/// or later by [`Arcy::drop_handle`][crate::Arcy::drop_handle]. Dropping the
+/// handle doesn't affect the async drop itself, see [`abort`][Self::abort] to
+/// cancel it.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "tokio", doc = "```")]
+#[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+/// # use arcy::{Arcy, AsyncDrop};
+/// # struct Foo;
+/// # impl AsyncDrop for Foo {
+/// #     async fn async_drop(self) {}
+/// # }
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (foo, last_foo) = Arcy::new(Foo);
+/// assert!(!last_foo.is_finished());
+///
+/// drop(foo);
+/// assert!(last_foo.wait().await.is_completed());
+/// # }
+/// ```
+pub struct DropHandle<E = Infallible> {
+    state: Arc<DropState>,
+    resume_panics: bool,
+    _error: PhantomData<fn() -> E>,
+}
+
+impl<E> DropHandle<E> {
+    /// Waits for the async drop to be over, like awaiting the handle itself.
+    pub async fn wait(self) -> DropOutcome<E>
+    where
+        E: 'static,
+    {
+        self.await
+    }
+
+    /// Returns `true` if awaiting the handle won't wait: the async drop is over,
+    /// or was [aborted][Self::abort].
+    pub fn is_finished(&self) -> bool {
+        self.state.is(REPORTED)
+    }
+
+    /// Cancels the async drop, and resolves the handle to
+    /// [`DropOutcome::Cancelled`] right away unless it's already over.
+    ///
+    /// If the async drop is running, it's dropped the next time the executor
+    /// polls it. If it hasn't started, e.g. because the value is still alive, it
+    /// never will: once the last `Arcy` is gone, the value is handed to the
+    /// [sync fallback][crate::ArcyBuilder::sync_fallback], if any, or dropped.
+    /// [`Arcy::on_dropped`][crate::Arcy::on_dropped] still waits for that.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// # use arcy::{Arcy, AsyncDrop};
+    /// struct Hung;
+    ///
+    /// impl AsyncDrop for Hung {
+    ///     async fn async_drop(self) {
+    ///         futures::future::pending::<()>().await;
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (hung, last_hung) = Arcy::new(Hung);
+    /// drop(hung);
+    /// last_hung.abort();
+    /// assert!(last_hung.await.is_cancelled());
+    ///
+    /// // aborted before it's released
+    /// let (hung, last_hung) = Arcy::new(Hung);
+    /// last_hung.abort();
+    /// assert!(last_hung.await.is_cancelled());
+    /// let dropped = Arcy::on_dropped(&hung);
+    /// drop(hung);
+    /// dropped.await;
+    /// # }
+    /// ```
+    pub fn abort(&self) {
+        self.state.abort();
+    }
+
+    /// Lets the async drop run without waiting for it, as dropping the handle
+    /// does.
+    ///
+    /// A new handle can then be obtained with
+    /// [`Arcy::drop_handle`][crate::Arcy::drop_handle].
+    pub fn detach(self) {}
+}
+
+impl<E> Future for DropHandle<E>
+where
+    E: 'static,
+{
+    type Output = DropOutcome<E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let outcome = {
+            let mut shared = self.state.shared.lock();
+            // set before the waker is taken
+            if !self.state.is(REPORTED) {
+                register(&mut shared.handle, cx);
+                return Poll::Pending;
+            }
+            shared.outcome.take()
+        };
+        match outcome.unwrap_or(DropOutcome::Cancelled) {
+            DropOutcome::Panicked(payload) if self.resume_panics => {
+                std::panic::resume_unwind(payload)
+            }
+            // the error was boxed by the async drop of the very value this handle was created for
+            outcome => Poll::Ready(outcome.map_err(|err| *err.downcast().unwrap())),
+        }
+    }
+}
+
+impl<E> Drop for DropHandle<E> {
+    fn drop(&mut self) {
+        // nothing wakes it up once reported
+        if !self.state.is(REPORTED) {
+            self.state.shared.lock().handle = None;
+        }
+        self.state.flags.fetch_and(!HAS_HANDLE, AcqRel);
+    }
+}
+
+impl<E> fmt::Debug for DropHandle<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DropHandle").finish_non_exhaustive()
+    }
+}
+
+/// Resolves once the async drop of a value is over, for as many waiters as
+/// needed.
+///
+/// Returned by [`Arcy::on_dropped`][crate::Arcy::on_dropped] and
+/// [`Weak::on_dropped`][crate::Weak::on_dropped]; clones resolve together. Unlike
+/// the [`DropHandle`], it doesn't tell how the async drop went. It also resolves
+/// if the async drop won't happen, because it was cancelled or the value was
+/// taken back with [`Arcy::try_unwrap`][crate::Arcy::try_unwrap].
+#[derive(Clone)]
+pub struct DropFuture {
+    state: Arc<DropState>,
+}
+
+impl Future for DropFuture {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.state.is(FINISHED) {
+            return Poll::Ready(());
+        }
+        let waiters = &mut self.state.shared.lock().waiters;
+        if self.state.is(FINISHED) {
+            return Poll::Ready(());
+        }
+        if !waiters.iter().any(|w| w.will_wake(cx.waker())) {
+            waiters.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+impl fmt::Debug for DropFuture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DropFuture").finish_non_exhaustive()
+    }
+}
+
+impl DropState {
+    /// Returns a new handle, unless there is one already.
+    pub(crate) fn handle<E>(self: &Arc<Self>, resume_panics: bool) -> Option<DropHandle<E>> {
+        if self.flags.fetch_or(HAS_HANDLE, AcqRel) & HAS_HANDLE != 0 {
+            return None;
+        }
+        Some(DropHandle {
+            state: Arc::clone(self),
+            resume_panics,
+            _error: PhantomData,
+        })
+    }
+
+    pub(crate) fn dropped(self: &Arc<Self>) -> DropFuture {
+        DropFuture {
+            state: Arc::clone(self),
+        }
+    }
+
+    pub(crate) fn is_aborted(&self) -> bool {
+        self.is(ABORTED)
+    }
+
+    /// Reports the outcome of the async drop once it's over, or won't happen,
+    /// unless it was already over or aborted.
+    pub(crate) fn finish(&self, outcome: DropOutcome<AnyError>) {
+        if self.is(FINISHED) {
+            return;
+        }
+        let (handle, waiters) = {
+            let mut shared = self.shared.lock();
+            if self.is(FINISHED) {
+                return;
+            }
+            if !self.is(REPORTED) {
+                shared.outcome = Some(outcome);
+            }
+            self.flags.fetch_or(FINISHED | REPORTED, AcqRel);
+            (shared.handle.take(), std::mem::take(&mut shared.waiters))
+        };
+        handle.into_iter().chain(waiters).for_each(Waker::wake);
+    }
+
+    /// Reports the async drop as cancelled, unless it's over: it's dropped
+    /// the next time it's polled, if it ever starts.
+    fn abort(&self) {
+        let handle = {
+            let mut shared = self.shared.lock();
+            if self.flags.fetch_or(ABORTED, AcqRel) & REPORTED != 0 {
+                return;
+            }
+            shared.outcome = Some(DropOutcome::Cancelled);
+            self.flags.fetch_or(REPORTED, AcqRel);
+            shared.handle.take()
+        };
+        if let Some(handle) = handle {
+            handle.wake();
+        }
+    }
+
+    fn is(&self, flag: u8) -> bool {
+        self.flags.load(Acquire) & flag != 0
+    }
+}
+
+fn register(slot: &mut Option<Waker>, cx: &Context<'_>) {
+    match slot {
+        Some(waker) if waker.will_wake(cx.waker()) => {}
+        _ => *slot = Some(cx.waker().clone()),
+    }
+}
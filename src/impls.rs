@@ -0,0 +1,190 @@
+//! [`AsyncDrop`] for containers of async droppable values, and wrappers
+//! adapting other values.
+
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+
+use futures::future::{self, OptionFuture};
+use futures::FutureExt;
+
+use crate::AsyncDrop;
+
+/// Async drops the value, if any.
+impl<T> AsyncDrop for Option<T>
+where
+    T: AsyncDrop,
+{
+    fn async_drop(self) -> impl Future<Output = ()> + Send {
+        OptionFuture::from(self.map(T::async_drop)).map(drop)
+    }
+}
+
+impl<T> AsyncDrop for Box<T>
+where
+    T: AsyncDrop,
+{
+    fn async_drop(self) -> impl Future<Output = ()> + Send {
+        (*self).async_drop()
+    }
+}
+
+/// Async drops the elements concurrently, see [`Sequential`] to drop them one
+/// after the other.
+impl<T> AsyncDrop for Vec<T>
+where
+    T: AsyncDrop,
+{
+    fn async_drop(self) -> impl Future<Output = ()> + Send {
+        future::join_all(self.into_iter().map(T::async_drop)).map(drop)
+    }
+}
+
+/// Async drops the elements concurrently, see [`Sequential`] to drop them one
+/// after the other.
+impl<T, const N: usize> AsyncDrop for [T; N]
+where
+    T: AsyncDrop,
+{
+    fn async_drop(self) -> impl Future<Output = ()> + Send {
+        future::join_all(self.map(T::async_drop)).map(drop)
+    }
+}
+
+macro_rules! tuple_impls {
+    ($last:ident) => {
+        impl<$last> AsyncDrop for ($last,)
+        where
+            $last: AsyncDrop,
+        {
+            fn async_drop(self) -> impl Future<Output = ()> + Send {
+                self.0.async_drop()
+            }
+        }
+    };
+    ($first:ident, $($rest:ident),+) => {
+        /// Async drops the elements concurrently.
+        impl<$first, $($rest),+> AsyncDrop for ($first, $($rest),+)
+        where
+            $first: AsyncDrop,
+            $($rest: AsyncDrop),+
+        {
+            #[allow(non_snake_case)]
+            fn async_drop(self) -> impl Future<Output = ()> + Send {
+                let ($first, $($rest),+) = self;
+                future::join($first.async_drop(), ($($rest,)+).async_drop()).map(drop)
+            }
+        }
+
+        tuple_impls!($($rest),+);
+    };
+}
+
+tuple_impls!(A, B, C, D, E, F, G, H);
+
+/// Async drops the elements of a collection one after the other, in iteration
+/// order.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "tokio", doc = "```")]
+#[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+/// use arcy::{Arcy, AsyncDrop, Sequential};
+///
+/// struct Conn(u32);
+///
+/// impl AsyncDrop for Conn {
+///     async fn async_drop(self) {
+///         println!("closing {}", self.0);
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (pool, last_pool) = Arcy::new(Sequential(vec![Conn(1), Conn(2)]));
+/// drop(pool);
+/// assert!(last_pool.await.is_completed());
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Sequential<C>(pub C);
+
+impl<C> AsyncDrop for Sequential<C>
+where
+    C: IntoIterator,
+    C::IntoIter: Send,
+    C::Item: AsyncDrop + Send,
+{
+    fn async_drop(self) -> impl Future<Output = ()> + Send {
+        let items = self.0.into_iter();
+        async move {
+            for item in items {
+                item.async_drop().await;
+            }
+        }
+    }
+}
+
+impl<C> Deref for Sequential<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.0
+    }
+}
+
+impl<C> DerefMut for Sequential<C> {
+    fn deref_mut(&mut self) -> &mut C {
+        &mut self.0
+    }
+}
+
+/// Puts a value without async cleanup into an [`Arcy`][crate::Arcy]: its
+/// async drop does nothing but drop it.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "tokio", doc = "```")]
+#[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+/// use std::collections::HashMap;
+///
+/// use arcy::{Arcy, SyncDrop};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (config, _) = Arcy::new(SyncDrop(HashMap::<String, String>::new()));
+/// assert!(config.is_empty());
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SyncDrop<T>(pub T);
+
+impl<T> SyncDrop<T> {
+    /// Returns the wrapped value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> AsyncDrop for SyncDrop<T>
+where
+    T: Send,
+{
+    // dropped when polled, like any other async drop
+    async fn async_drop(self) {
+        drop(self)
+    }
+}
+
+impl<T> Deref for SyncDrop<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for SyncDrop<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
@@ -0,0 +1,226 @@
+//! The reference counted allocation shared by [`Arcy`][crate::Arcy] and
+//! [`Weak`][crate::Weak] pointers.
+
+use std::mem::ManuallyDrop;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+use crate::sync::{fence, AtomicUsize, UnsafeCell};
+
+/// A value together with its strong count and a header `H` describing what
+/// to do with the value once it's released.
+///
+/// The value is never dropped in place: whoever releases the last strong
+/// reference becomes its sole owner and moves it out with [`ArcyInner::take`],
+/// usually to hand it over to a drop task. This way no handle needs to be the
+/// unique owner of the allocation itself, which is what made `Arc::try_unwrap`
+/// racy.
+pub(crate) struct ArcyInner<T: ?Sized, H = ()> {
+    strong: AtomicUsize,
+    pub(crate) header: H,
+    value: UnsafeCell<ManuallyDrop<T>>,
+}
+
+// The value is shared between threads while the strong count is positive and
+// then moved to whichever thread releases the last reference.
+unsafe impl<T: ?Sized + Send + Sync, H: Send + Sync> Send for ArcyInner<T, H> {}
+unsafe impl<T: ?Sized + Send + Sync, H: Send + Sync> Sync for ArcyInner<T, H> {}
+
+impl<T, H> ArcyInner<T, H> {
+    /// Creates an allocation holding one strong reference.
+    pub(crate) fn new(value: T, header: H) -> Self {
+        Self {
+            strong: AtomicUsize::new(1),
+            header,
+            value: UnsafeCell::new(ManuallyDrop::new(value)),
+        }
+    }
+
+    /// Moves the value out.
+    ///
+    /// # Safety
+    ///
+    /// Must only be called once, after [`release`][Self::release] or
+    /// [`release_unique`][Self::release_unique] returned `true`.
+    pub(crate) unsafe fn take(&self) -> T {
+        self.value.with_mut(|value| ManuallyDrop::take(&mut *value))
+    }
+}
+
+impl<T: ?Sized, H> ArcyInner<T, H> {
+    /// Adds a strong reference. The caller must already hold one.
+    pub(crate) fn acquire(&self) {
+        // Using a relaxed ordering is alright here, see inner doc of Arc::clone
+        self.strong.fetch_add(1, Relaxed);
+    }
+
+    /// Adds a strong reference unless the count already dropped to zero.
+    ///
+    /// Once the count reached zero the value belongs to whoever released it
+    /// and must never be handed out again.
+    pub(crate) fn try_acquire(&self) -> bool {
+        let mut n = self.strong.load(Relaxed);
+        loop {
+            if n == 0 {
+                return false;
+            }
+            match self
+                .strong
+                .compare_exchange_weak(n, n + 1, Acquire, Relaxed)
+            {
+                Ok(_) => return true,
+                Err(old) => n = old,
+            }
+        }
+    }
+
+    /// Releases a strong reference.
+    ///
+    /// Returns `true` if it was the last one, in which case the caller is now
+    /// the only party with access to the value and must move it out with
+    /// [`take`][Self::take].
+    pub(crate) fn release(&self) -> bool {
+        // see std::sync::Arc drop impl for comments about why this is safe
+        if self.strong.fetch_sub(1, Release) != 1 {
+            return false;
+        }
+        fence(Acquire);
+        true
+    }
+
+    /// Releases the strong reference if it's the only one.
+    ///
+    /// Returns `true` on success, in which case the caller owns the value just
+    /// like after [`release`][Self::release] returned `true`. Otherwise the
+    /// count is left untouched.
+    pub(crate) fn release_unique(&self) -> bool {
+        if self
+            .strong
+            .compare_exchange(1, 0, Relaxed, Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        // synchronizes with the `Release` decrements of the other references
+        fence(Acquire);
+        true
+    }
+
+    /// Returns the number of strong references.
+    pub(crate) fn strong_count(&self) -> usize {
+        self.strong.load(Relaxed)
+    }
+
+    /// Returns a reference to the value.
+    ///
+    /// # Safety
+    ///
+    /// The caller must hold a strong reference for the whole lifetime of the
+    /// returned reference.
+    pub(crate) unsafe fn get(&self) -> &T {
+        self.value.with(|value| &**value)
+    }
+
+    /// Returns a raw pointer to the value.
+    pub(crate) fn as_ptr(&self) -> *const T {
+        // `ManuallyDrop<T>` has the same layout as `T`
+        self.value.with(|value| value as *const T)
+    }
+
+    /// Returns a type erased pointer to the value, for it to be moved out
+    /// by code that knows its concrete type.
+    ///
+    /// The same rules as for [`take`][Self::take] apply to moving the value out.
+    pub(crate) fn value_ptr(&self) -> *mut () {
+        self.value.with_mut(|value| value as *mut ())
+    }
+
+    /// Returns a mutable reference to the value.
+    ///
+    /// Exclusive access to the allocation rules out any other strong reference.
+    pub(crate) fn get_mut(&mut self) -> &mut T {
+        self.value.with_mut(|value| unsafe { &mut **value })
+    }
+}
+
+#[cfg(all(test, loom))]
+mod tests {
+    use super::*;
+
+    use loom::sync::Arc;
+    use loom::thread;
+
+    fn inner(value: &str) -> Arc<ArcyInner<String>> {
+        Arc::new(ArcyInner::new(value.to_string(), ()))
+    }
+
+    #[test]
+    fn last_release_takes_value_once() {
+        loom::model(|| {
+            let inner = inner("foo");
+            inner.acquire();
+
+            let other = Arc::clone(&inner);
+            let th = thread::spawn(move || {
+                assert_eq!(unsafe { other.get() }, "foo");
+                other.release().then(|| unsafe { other.take() })
+            });
+
+            assert_eq!(unsafe { inner.get() }, "foo");
+            let ours = inner.release().then(|| unsafe { inner.take() });
+            let theirs = th.join().unwrap();
+
+            match (ours, theirs) {
+                (Some(value), None) | (None, Some(value)) => assert_eq!(value, "foo"),
+                other => panic!("value taken {:?}", other),
+            }
+        });
+    }
+
+    #[test]
+    fn release_unique_takes_value_once() {
+        loom::model(|| {
+            let inner = inner("foo");
+            inner.acquire();
+
+            let other = Arc::clone(&inner);
+            let th = thread::spawn(move || other.release().then(|| unsafe { other.take() }));
+
+            let ours = inner.release_unique().then(|| unsafe { inner.take() });
+            let theirs = th.join().unwrap();
+
+            match (ours, theirs) {
+                (Some(value), None) | (None, Some(value)) => assert_eq!(value, "foo"),
+                // we lost the race and kept our reference
+                (None, None) => {
+                    assert!(inner.release());
+                    assert_eq!(unsafe { inner.take() }, "foo");
+                }
+                other => panic!("value taken {:?}", other),
+            }
+        });
+    }
+
+    #[test]
+    fn upgrade_never_resurrects() {
+        loom::model(|| {
+            let inner = inner("foo");
+
+            let weak = Arc::clone(&inner);
+            let th = thread::spawn(move || {
+                if !weak.try_acquire() {
+                    return None;
+                }
+                assert_eq!(unsafe { weak.get() }, "foo");
+                weak.release().then(|| unsafe { weak.take() })
+            });
+
+            let ours = inner.release().then(|| unsafe { inner.take() });
+            let theirs = th.join().unwrap();
+
+            match (ours, theirs) {
+                (Some(value), None) | (None, Some(value)) => assert_eq!(value, "foo"),
+                other => panic!("value taken {:?}", other),
+            }
+        });
+    }
+}
@@ -8,17 +8,48 @@
     clippy::clone_on_ref_ptr
 )]
 
-use std::sync::{
-    atomic::{
-        self, AtomicUsize,
-        Ordering::{Acquire, Relaxed, Release},
-    },
-    Arc,
-};
-
-use async_trait::async_trait;
-use tokio::sync::Notify;
-use tokio::task::JoinHandle;
+mod builder;
+#[cfg(feature = "async-trait")]
+pub mod compat;
+mod dependency;
+mod error;
+pub mod executor;
+mod fallback;
+mod handle;
+mod impls;
+mod inner;
+mod limiter;
+mod outcome;
+mod panic;
+mod release;
+mod strategy;
+mod sync;
+mod timer;
+mod tracker;
+mod unsize;
+
+use std::convert::Infallible;
+use std::fmt;
+use std::future::Future;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::Arc;
+
+pub use crate::builder::ArcyBuilder;
+pub use crate::error::{CycleError, Elapsed, TryNewError};
+pub use crate::executor::DropExecutor;
+pub use crate::handle::{DropFuture, DropHandle};
+pub use crate::impls::{Sequential, SyncDrop};
+use crate::inner::ArcyInner;
+pub use crate::limiter::DropLimiter;
+pub use crate::outcome::DropOutcome;
+pub use crate::panic::{PanicCallback, PanicPolicy};
+use crate::release::Header;
+pub use crate::strategy::DropStrategy;
+pub use crate::tracker::DropTracker;
+#[cfg(feature = "derive")]
+pub use arcy_derive::AsyncDrop;
+use futures::FutureExt;
 
 /// Like a [`Arc`][arc] but invokes [`async_drop`][async_drop] when the last `Arcy` pointer
 /// is destroyed.
@@ -30,6 +61,17 @@ use tokio::task::JoinHandle;
 /// on the value stored in that allocation (often referred to as “inner value”),
 /// and after the completion of that async function, the value is dropped  
 ///
+/// No task is spawned while the value is alive: the async drop is spawned on the
+/// [executor][DropExecutor] the `Arcy` was created with only once the last pointer
+/// is gone, no matter which thread drops it.
+#[cfg_attr(feature = "tokio", doc = "[`Arcy::new`]")]
+#[cfg_attr(not(feature = "tokio"), doc = "`Arcy::new`")]
+/// uses the Tokio runtime it's called from, [`Arcy::new_in`] accepts any
+/// executor.
+///
+/// `T` may be unsized: an `Arcy<T>` can be turned into an `Arcy<dyn Trait>` with
+/// [`unsize!`], and still runs `T`'s async drop.
+///
 /// Shared references in Rust disallow mutation by default, and `Arcy` is no exception:
 /// you cannot generally obtain a mutable reference to something inside an `Arcy`.
 /// If you need to mutate through an `Arcy`, [`Mutex`][mutex], [`RwLock`][rwlock], or one of the [`Atomic`][atomic]
@@ -41,16 +83,18 @@ use tokio::task::JoinHandle;
 /// clashes with `T`'s methods, the methods of `Arcy<T>` itself are associated
 /// functions, called using [fully qualified syntax].
 ///
-/// `Arcy<T>`'s implementations of [`clone`][clone] is an associated async function.
+/// `Arcy<T>`'s implementation of [`Clone`] is synchronous, so an `Arcy<T>` can be used
+/// wherever a `Clone` bound is required. Prefer `foo.clone()` or `Clone::clone(&foo)`
+/// over `Arcy::clone(&foo)`, which still refers to the deprecated async associated function.
 ///
 /// # Examples
 ///
-/// ```
+#[cfg_attr(feature = "tokio", doc = "```")]
+#[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
 /// use arcy::{Arcy, AsyncDrop};
 ///
 /// struct Foo {}
 ///
-/// #[async_trait::async_trait]
 /// impl AsyncDrop for Foo {
 ///     async fn async_drop(self) {
 ///         // do something asynchronously
@@ -63,13 +107,13 @@ use tokio::task::JoinHandle;
 ///
 /// #[tokio::main]
 /// async fn main() {
-///     let (foo, last_foo) = Arcy::new(Foo {}).await;
-///     let j1 = tokio::spawn(do_something(Arcy::clone(&foo).await));
+///     let (foo, last_foo) = Arcy::new(Foo {});
+///     let j1 = tokio::spawn(do_something(foo.clone()));
 ///     let j2 = tokio::spawn(do_something(foo));
 ///
 ///     tokio::try_join!(j1, j2).unwrap();
 ///
-///     last_foo.await.unwrap();
+///     assert!(last_foo.await.is_completed());
 /// }
 /// ```
 ///
@@ -81,74 +125,674 @@ use tokio::task::JoinHandle;
 /// [atomic]: core::sync::atomic
 /// [deref]: core::ops::Deref
 /// [fully qualified syntax]: https://doc.rust-lang.org/book/ch19-03-advanced-traits.html#fully-qualified-syntax-for-disambiguation-calling-methods-with-the-same-name
-#[derive(Debug)]
-pub struct Arcy<T>
-where
-    T: AsyncDrop,
-{
-    inner: Arc<ArcyInner<T>>,
-    notify: Arc<Notify>,
+pub struct Arcy<T: ?Sized> {
+    inner: Arc<ArcyInner<T, Header>>,
+}
+
+/// `Weak` is a version of [`Arcy`] that holds a non-owning reference to the
+/// managed allocation. The allocation is accessed by calling [`upgrade`][Weak::upgrade]
+/// on the `Weak` pointer, which returns an [`Option`]`<`[`Arcy`]`<T>>`.
+///
+/// Since a `Weak` reference does not count towards ownership, it will not
+/// prevent the value from being async dropped. Once the last [`Arcy`] is gone
+/// and the drop task has been signalled, `upgrade` always returns [`None`];
+/// a value that has been queued for [`async_drop`][AsyncDrop::async_drop] is never
+/// resurrected.
+///
+/// A `Weak` pointer is useful for keeping a temporary reference to the allocation
+/// without preventing its inner value from being dropped, e.g. in caches or
+/// back-references.
+///
+/// The typical way to obtain a `Weak` pointer is to call [`Arcy::downgrade`].
+pub struct Weak<T: ?Sized> {
+    inner: std::sync::Weak<ArcyInner<T, Header>>,
+    // the header goes away with the allocation, possibly before the async drop is over
+    dropped: DropFuture,
 }
 
 /// Called when an [`Arcy`] is destroyed.
-#[async_trait]
+///
+/// Implement it with an `async fn`; the future runs inline in the drop task, without
+/// being boxed. Impls written with [`async_trait`] can keep working through
+#[cfg_attr(feature = "async-trait", doc = "[`compat::AsyncDrop`],")]
+#[cfg_attr(not(feature = "async-trait"), doc = "`compat::AsyncDrop`,")]
+/// with the `async-trait` feature.
+///
+/// It's implemented for `Option`, `Box`, `Vec`, arrays and tuples of async droppable
+/// values; [`Sequential`] and [`SyncDrop`] adapt other values.
+///
+/// [`async_trait`]: https://docs.rs/async-trait
+///
+/// # Examples
+///
+/// ```
+/// use arcy::AsyncDrop;
+///
+/// struct Foo;
+///
+/// impl AsyncDrop for Foo {
+///     async fn async_drop(self) {
+///         // do something asynchronously
+///     }
+/// }
+/// ```
 pub trait AsyncDrop {
-    async fn async_drop(self);
+    fn async_drop(self) -> impl Future<Output = ()> + Send;
 }
 
-#[derive(Debug)]
-struct ArcyInner<T>(T, AtomicUsize);
+/// Like [`AsyncDrop`], but the cleanup can fail.
+///
+/// The error is reported as [`DropOutcome::Failed`] to the [`DropHandle`] returned
+/// when the [`Arcy`] was created, so that the owner of the value can observe it.
+/// Every [`AsyncDrop`] is a `TryAsyncDrop` that never fails.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "tokio", doc = "```")]
+#[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+/// use arcy::{Arcy, DropOutcome, TryAsyncDrop};
+///
+/// struct Tx;
+///
+/// impl TryAsyncDrop for Tx {
+///     type Error = std::io::Error;
+///
+///     async fn try_async_drop(self) -> Result<(), Self::Error> {
+///         Err(std::io::Error::new(std::io::ErrorKind::Other, "commit failed"))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (tx, last_tx) = Arcy::new(Tx);
+/// drop(tx);
+/// match last_tx.await {
+///     DropOutcome::Failed(err) => assert_eq!(err.to_string(), "commit failed"),
+///     outcome => panic!("unexpected {:?}", outcome),
+/// }
+/// # }
+/// ```
+pub trait TryAsyncDrop {
+    type Error: Send + 'static;
+
+    fn try_async_drop(self) -> impl Future<Output = Result<(), Self::Error>> + Send;
+}
+
+impl<T> TryAsyncDrop for T
+where
+    T: AsyncDrop + Send + 'static,
+{
+    type Error = Infallible;
+
+    fn try_async_drop(self) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        self.async_drop().map(Ok)
+    }
+}
 
 impl<T> Arcy<T>
 where
-    T: AsyncDrop + Send + Sync + 'static,
+    T: TryAsyncDrop + Send + Sync + 'static,
 {
     /// Constructs a new `Arcy<T>`.
-    pub async fn new(inner: T) -> (Self, JoinHandle<()>) {
-        let inner = Arc::new(ArcyInner(inner, AtomicUsize::new(1)));
-        let notify = Arc::new(Notify::new());
-        let slayer = tokio::spawn(Self::slayer(Arc::clone(&notify), Arc::clone(&inner)));
-        (Self { inner, notify }, slayer)
+    ///
+    /// The returned [`DropHandle`] resolves to a [`DropOutcome`] once the last `Arcy`
+    /// has been dropped and [`AsyncDrop::async_drop`] is over.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside the context of a Tokio runtime, see [`Arcy::try_new`]
+    /// and [`Handle::try_current`][tokio::runtime::Handle::try_current].
+    #[cfg(feature = "tokio")]
+    pub fn new(value: T) -> (Self, DropHandle<T::Error>) {
+        Self::try_new(value).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Constructs a new `Arcy<T>`, failing if there is no current Tokio runtime.
+    ///
+    /// The value is handed back inside the [`TryNewError`]. The async drop is
+    /// spawned on the runtime through its [`Handle`][tokio::runtime::Handle],
+    /// captured now.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use arcy::{Arcy, AsyncDrop};
+    /// # struct Foo;
+    /// # impl AsyncDrop for Foo {
+    /// #     async fn async_drop(self) {}
+    /// # }
+    /// assert!(Arcy::try_new(Foo).is_err());
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn try_new(value: T) -> Result<(Self, DropHandle<T::Error>), TryNewError<T>> {
+        Self::builder().try_build(value)
+    }
+
+    /// Constructs a new `Arcy<T>` whose async drop will be spawned on `executor`.
+    ///
+    /// See the [`executor`] module for the available executors. With a Tokio
+    #[cfg_attr(feature = "tokio", doc = "[`Handle`][tokio::runtime::Handle],")]
+    #[cfg_attr(not(feature = "tokio"), doc = "`Handle`,")]
+    /// the async drop runs on that runtime whichever thread drops the last `Arcy`.
+    pub fn new_in(executor: impl DropExecutor, value: T) -> (Self, DropHandle<T::Error>) {
+        Self::builder().executor(executor).build(value)
+    }
+
+    /// Returns an [`ArcyBuilder`] to configure how the value is async dropped.
+    pub fn builder() -> ArcyBuilder<T> {
+        ArcyBuilder::new()
+    }
+
+    /// Returns a new [`DropHandle`] to the async drop of the value, if there's no
+    /// other one: the handle returned at construction must have been dropped or
+    /// [detached][DropHandle::detach].
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// # use arcy::{Arcy, AsyncDrop};
+    /// # struct Foo;
+    /// # impl AsyncDrop for Foo {
+    /// #     async fn async_drop(self) {}
+    /// # }
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (foo, last_foo) = Arcy::new(Foo);
+    /// assert!(Arcy::drop_handle(&foo).is_none());
+    ///
+    /// last_foo.detach();
+    /// let last_foo = Arcy::drop_handle(&foo).unwrap();
+    /// drop(foo);
+    /// assert!(last_foo.await.is_completed());
+    /// # }
+    /// ```
+    pub fn drop_handle(this: &Self) -> Option<DropHandle<T::Error>> {
+        this.inner.header.handle()
     }
 
+    /// Async form of [`Clone::clone`], kept for backwards compatibility.
+    #[deprecated(
+        note = "`Arcy` implements `Clone`, use `foo.clone()` or `Clone::clone(&foo)` instead"
+    )]
     pub async fn clone(this: &Self) -> Self {
-        // Using a relaxed ordering is alright here, see inner doc of Arc::clone
-        this.inner.1.fetch_add(1, Relaxed);
+        Clone::clone(this)
+    }
 
-        let inner = Arc::clone(&this.inner);
-        let notify = Arc::clone(&this.notify);
-        Self { inner, notify }
+    /// Makes a mutable reference into the given `Arcy`.
+    ///
+    /// If there are other `Arcy` pointers to the same allocation, the inner value
+    /// is cloned into a new allocation to ensure unique ownership. The displaced
+    /// value is still async dropped once its last `Arcy` is gone, and is the one the
+    /// [`DropHandle`] keeps waiting for; the copy is async dropped the same way, but
+    /// its outcome isn't reported.
+    ///
+    /// If there are no other `Arcy` pointers, but some [`Weak`] ones, the value is
+    /// moved to a new allocation and the `Weak` pointers can't be upgraded anymore.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// # use arcy::{Arcy, AsyncDrop};
+    /// #[derive(Clone)]
+    /// struct Foo(u32);
+    ///
+    /// impl AsyncDrop for Foo {
+    ///     async fn async_drop(self) {
+    ///         println!("dropping {}", self.0);
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (mut foo, last_foo) = Arcy::new(Foo(3));
+    /// let other_foo = foo.clone();
+    ///
+    /// Arcy::make_mut(&mut foo).0 = 4;
+    /// assert_eq!((foo.0, other_foo.0), (4, 3));
+    ///
+    /// drop(other_foo);
+    /// assert!(last_foo.await.is_completed());
+    /// # }
+    /// ```
+    pub fn make_mut(this: &mut Self) -> &mut T
+    where
+        T: Clone,
+    {
+        if Arc::get_mut(&mut this.inner).is_none() {
+            if this.inner.release_unique() {
+                // only weak pointers are left: the value is ours to move
+                let value = unsafe { this.inner.take() };
+                let header = this.inner.header.relocate();
+                this.inner = Arc::new(ArcyInner::new(value, header));
+            } else {
+                let value = T::clone(this);
+                let header = this.inner.header.fork();
+                *this = Self {
+                    inner: Arc::new(ArcyInner::new(value, header)),
+                };
+            }
+        }
+        // the allocation was either unique or is a new one
+        Self::get_mut(this).expect("unique allocation")
     }
 
-    async fn slayer(notify: Arc<Notify>, inner: Arc<ArcyInner<T>>) {
-        notify.notified().await;
-        // we are guaranteed to be the last holder of inner
-        let inner = Arc::try_unwrap(inner).unwrap_or_else(|_| unreachable!());
-        inner.0.async_drop().await;
+    /// Drops this `Arcy` and, if it was the last strong reference, runs the async
+    /// drop of the value on the current task rather than spawning it.
+    ///
+    /// Returns the outcome of the async drop once it's over, or [`None`] if other
+    /// `Arcy`s are left. The async drop is otherwise run as configured by the
+    /// [`ArcyBuilder`]; with [`PanicPolicy::Resume`] its panic is resumed here.
+    /// The [`DropHandle`] resolves to the same outcome, but for the error of
+    /// [`DropOutcome::Failed`] and the payload of [`DropOutcome::Panicked`]: these
+    /// are only returned here, and the handle resolves to
+    /// [`DropOutcome::Cancelled`] instead.
+    ///
+    /// Nothing happens until the returned future is polled: if it's dropped before
+    /// then, the `Arcy` is dropped as usual. Once polled, the async drop runs as
+    /// part of the returned future: dropping it before it's over drops the value
+    /// as is, without finishing its async drop, and the handle resolves to
+    /// [`DropOutcome::Cancelled`]. The value only goes to the
+    /// [fallback][ArcyBuilder::sync_fallback] if its async drop hadn't started,
+    /// e.g. waiting for a [limiter][ArcyBuilder::limiter].
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// # use arcy::{Arcy, AsyncDrop};
+    /// # struct Foo;
+    /// # impl AsyncDrop for Foo {
+    /// #     async fn async_drop(self) {}
+    /// # }
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (foo, last_foo) = Arcy::new(Foo);
+    /// let other_foo = foo.clone();
+    ///
+    /// assert!(Arcy::release(other_foo).await.is_none());
+    /// // the async drop is over by now
+    /// assert!(Arcy::release(foo).await.unwrap().is_completed());
+    /// assert!(last_foo.await.is_completed());
+    /// # }
+    /// ```
+    pub async fn release(this: Self) -> Option<DropOutcome<T::Error>> {
+        let inner = Self::into_raw_inner(this);
+        if !inner.release() {
+            return None;
+        }
+        // we released the last strong reference: the value is ours
+        let value = unsafe { inner.take() };
+        let drop = inner.header.release_inline(value);
+        Some(drop.await)
     }
 }
 
-impl<T> Drop for Arcy<T>
-where
-    T: AsyncDrop,
-{
+impl<T> Arcy<T> {
+    /// Returns the inner value, if the `Arcy` has exactly one strong reference.
+    ///
+    /// Otherwise, an [`Err`] is returned with the same `Arcy` that was passed in.
+    ///
+    /// The value is handed back without going through [`AsyncDrop::async_drop`]:
+    /// the [`DropHandle`] resolves to [`DropOutcome::Cancelled`].
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// # use arcy::{Arcy, AsyncDrop};
+    /// # #[derive(Debug, PartialEq)]
+    /// # struct Foo(u32);
+    /// # impl AsyncDrop for Foo {
+    /// #     async fn async_drop(self) {}
+    /// # }
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (foo, last_foo) = Arcy::new(Foo(3));
+    /// let other_foo = foo.clone();
+    ///
+    /// let foo = Arcy::try_unwrap(foo).unwrap_err();
+    /// drop(other_foo);
+    /// assert_eq!(Arcy::try_unwrap(foo).unwrap(), Foo(3));
+    /// assert!(last_foo.await.is_cancelled());
+    /// # }
+    /// ```
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        if !this.inner.release_unique() {
+            return Err(this);
+        }
+        let inner = Self::into_raw_inner(this);
+        // we released the only strong reference: the value is ours
+        let value = unsafe { inner.take() };
+        inner.header.cancel();
+        Ok(value)
+    }
+
+    /// Returns the inner value, if the `Arcy` has exactly one strong reference.
+    ///
+    /// Otherwise, [`None`] is returned and the `Arcy` is dropped. If several
+    /// `Arcy`s race to call `into_inner`, exactly one of them gets the value,
+    /// which [`try_unwrap`][Self::try_unwrap] doesn't guarantee.
+    ///
+    /// Like with `try_unwrap`, the value is handed back without going through
+    /// [`AsyncDrop::async_drop`].
+    pub fn into_inner(this: Self) -> Option<T> {
+        let inner = Self::into_raw_inner(this);
+        if !inner.release() {
+            return None;
+        }
+        // we released the last strong reference: the value is ours
+        let value = unsafe { inner.take() };
+        inner.header.cancel();
+        Some(value)
+    }
+}
+
+impl<T: ?Sized> Arcy<T> {
+    /// Creates a new [`Weak`] pointer to this allocation.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// # use arcy::{Arcy, AsyncDrop};
+    /// # struct Foo;
+    /// # impl AsyncDrop for Foo {
+    /// #     async fn async_drop(self) {}
+    /// # }
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (foo, last_foo) = Arcy::new(Foo);
+    /// let weak_foo = Arcy::downgrade(&foo);
+    /// assert!(weak_foo.upgrade().is_some());
+    ///
+    /// drop(foo);
+    /// last_foo.await;
+    /// assert!(weak_foo.upgrade().is_none());
+    /// # }
+    /// ```
+    pub fn downgrade(this: &Self) -> Weak<T> {
+        let inner = Arc::downgrade(&this.inner);
+        let dropped = this.inner.header.dropped();
+        Weak { inner, dropped }
+    }
+
+    /// Returns a mutable reference into the given `Arcy`, if there are no other
+    /// `Arcy` or [`Weak`] pointers to the same allocation.
+    ///
+    /// Returns [`None`] otherwise, because it is not safe to mutate a shared value.
+    /// See also [`make_mut`][Self::make_mut], which will clone the inner value
+    /// when there are other pointers.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// # use arcy::{Arcy, AsyncDrop};
+    /// # struct Foo(u32);
+    /// # impl AsyncDrop for Foo {
+    /// #     async fn async_drop(self) {}
+    /// # }
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (mut foo, _) = Arcy::new(Foo(3));
+    /// Arcy::get_mut(&mut foo).unwrap().0 = 4;
+    /// assert_eq!(foo.0, 4);
+    ///
+    /// let other_foo = foo.clone();
+    /// assert!(Arcy::get_mut(&mut foo).is_none());
+    /// # }
+    /// ```
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        let inner = Arc::get_mut(&mut this.inner)?;
+        Some(inner.get_mut())
+    }
+
+    /// Gets the number of `Arcy` pointers to this allocation.
+    ///
+    /// Other threads may change the count at any time, so this is mostly
+    /// useful for diagnostics.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// # use arcy::{Arcy, AsyncDrop};
+    /// # struct Foo;
+    /// # impl AsyncDrop for Foo {
+    /// #     async fn async_drop(self) {}
+    /// # }
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (foo, _) = Arcy::new(Foo);
+    /// let _also_foo = foo.clone();
+    /// let _weak_foo = Arcy::downgrade(&foo);
+    ///
+    /// assert_eq!(Arcy::strong_count(&foo), 2);
+    /// assert_eq!(Arcy::weak_count(&foo), 1);
+    /// # }
+    /// ```
+    pub fn strong_count(this: &Self) -> usize {
+        this.inner.strong_count()
+    }
+
+    /// Gets the number of [`Weak`] pointers to this allocation.
+    pub fn weak_count(this: &Self) -> usize {
+        Arc::weak_count(&this.inner)
+    }
+
+    /// Returns `true` if the two `Arcy`s point to the same allocation.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// # use arcy::{Arcy, AsyncDrop};
+    /// # struct Foo;
+    /// # impl AsyncDrop for Foo {
+    /// #     async fn async_drop(self) {}
+    /// # }
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (foo, _) = Arcy::new(Foo);
+    /// let (other_foo, _) = Arcy::new(Foo);
+    ///
+    /// assert!(Arcy::ptr_eq(&foo, &foo.clone()));
+    /// assert!(!Arcy::ptr_eq(&foo, &other_foo));
+    /// # }
+    /// ```
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.inner, &other.inner)
+    }
+
+    /// Returns a future resolving once the async drop of the value is over, e.g.
+    /// to wait for a port to be released from any of the `Arcy`s sharing it.
+    ///
+    /// The future can be cloned and awaited by many tasks; it doesn't keep the
+    /// value alive, see [`DropFuture`].
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// # use arcy::{Arcy, AsyncDrop};
+    /// # struct Listener;
+    /// # impl AsyncDrop for Listener {
+    /// #     async fn async_drop(self) {}
+    /// # }
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (listener, _) = Arcy::new(Listener);
+    /// let dropped = Arcy::on_dropped(&listener);
+    ///
+    /// let waiters: Vec<_> = (0..3).map(|_| tokio::spawn(dropped.clone())).collect();
+    /// drop(listener);
+    /// for waiter in waiters {
+    ///     waiter.await.unwrap();
+    /// }
+    /// # }
+    /// ```
+    pub fn on_dropped(this: &Self) -> DropFuture {
+        this.inner.header.dropped()
+    }
+
+    /// Provides a raw pointer to the value.
+    ///
+    /// The pointer is valid as long as there are strong references to the
+    /// allocation.
+    pub fn as_ptr(this: &Self) -> *const T {
+        this.inner.as_ptr()
+    }
+
+    /// Consumes the `Arcy` without releasing its strong reference.
+    fn into_raw_inner(this: Self) -> Arc<ArcyInner<T, Header>> {
+        let this = ManuallyDrop::new(this);
+        // `this` is never used nor dropped again
+        unsafe { ptr::read(&this.inner) }
+    }
+}
+
+impl<T: ?Sized> Weak<T> {
+    /// Attempts to upgrade the `Weak` pointer to an [`Arcy`].
+    ///
+    /// Returns [`None`] if the inner value has already been handed over to
+    /// [`AsyncDrop::async_drop`] (or is about to be).
+    pub fn upgrade(&self) -> Option<Arcy<T>> {
+        let inner = self.inner.upgrade()?;
+        if !inner.try_acquire() {
+            return None;
+        }
+        Some(Arcy { inner })
+    }
+
+    /// Gets the number of [`Arcy`] pointers to the allocation.
+    ///
+    /// Returns 0 once the last one is gone.
+    pub fn strong_count(&self) -> usize {
+        self.inner.upgrade().map_or(0, |inner| inner.strong_count())
+    }
+
+    /// Gets the number of `Weak` pointers to the allocation, including this one.
+    ///
+    /// Returns 0 if the allocation itself is gone.
+    pub fn weak_count(&self) -> usize {
+        self.inner.weak_count()
+    }
+
+    /// Returns `true` if the two `Weak`s point to the same allocation.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        std::sync::Weak::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Returns `true` if the last [`Arcy`] is gone, i.e. the value has been handed
+    /// over to [`AsyncDrop::async_drop`] (or taken back with [`Arcy::into_inner`])
+    /// and [`upgrade`][Self::upgrade] will never succeed again.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// # use arcy::{Arcy, AsyncDrop};
+    /// # struct Foo;
+    /// # impl AsyncDrop for Foo {
+    /// #     async fn async_drop(self) {}
+    /// # }
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (foo, _) = Arcy::new(Foo);
+    /// let weak_foo = Arcy::downgrade(&foo);
+    /// assert!(!weak_foo.is_dropping());
+    ///
+    /// drop(foo);
+    /// assert!(weak_foo.is_dropping());
+    /// # }
+    /// ```
+    pub fn is_dropping(&self) -> bool {
+        self.strong_count() == 0
+    }
+
+    /// Returns a future resolving once the async drop of the value is over, see
+    /// [`Arcy::on_dropped`].
+    ///
+    /// Works even after the last [`Arcy`] is gone.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "tokio", doc = "```")]
+    #[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+    /// # use arcy::{Arcy, AsyncDrop};
+    /// # struct Foo;
+    /// # impl AsyncDrop for Foo {
+    /// #     async fn async_drop(self) {}
+    /// # }
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (foo, _) = Arcy::new(Foo);
+    /// let weak_foo = Arcy::downgrade(&foo);
+    ///
+    /// drop(foo);
+    /// weak_foo.on_dropped().await;
+    /// # }
+    /// ```
+    pub fn on_dropped(&self) -> DropFuture {
+        self.dropped.clone()
+    }
+}
+
+impl<T: ?Sized> Clone for Arcy<T> {
+    fn clone(&self) -> Self {
+        self.inner.acquire();
+        let inner = Arc::clone(&self.inner);
+        Self { inner }
+    }
+}
+
+impl<T: ?Sized> Clone for Weak<T> {
+    fn clone(&self) -> Self {
+        let inner = std::sync::Weak::clone(&self.inner);
+        let dropped = self.dropped.clone();
+        Self { inner, dropped }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Weak<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(Weak)")
+    }
+}
+
+impl<T: ?Sized> Drop for Arcy<T> {
     fn drop(&mut self) {
-        // see std::sync::Arc drop impl for comments about why this is safe
-        if self.inner.1.fetch_sub(1, Release) != 1 {
+        if !self.inner.release() {
             return;
         }
-        atomic::fence(Acquire);
-        self.notify.notify_one();
+        // we were the last strong reference: the value is ours to hand over
+        unsafe { self.inner.header.release(self.inner.value_ptr()) };
     }
 }
 
-impl<T> std::ops::Deref for Arcy<T>
-where
-    T: AsyncDrop,
-{
+impl<T: ?Sized> std::ops::Deref for Arcy<T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        &self.inner.0
+        // we hold a strong reference for as long as the borrow of self lasts
+        unsafe { self.inner.get() }
+    }
+}
+
+impl<T> fmt::Debug for Arcy<T>
+where
+    T: fmt::Debug + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
     }
 }
+
+#[doc(hidden)]
+pub mod __private {
+    //! Used by the code generated by `#[derive(AsyncDrop)]`.
+
+    pub use futures::future::join;
+}
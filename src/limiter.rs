@@ -0,0 +1,209 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+use parking_lot::{const_rwlock, Mutex, RwLock};
+
+/// Limits how many async drops run at the same time.
+///
+/// Values are put under a limiter with [`ArcyBuilder::limiter`][crate::ArcyBuilder::limiter],
+/// or with [`DropLimiter::set_global`] for all the values created afterwards without
+/// a limiter of their own. Once their last [`Arcy`][crate::Arcy] is gone, their
+/// [`AsyncDrop::async_drop`][crate::AsyncDrop::async_drop] waits for one of the
+/// limiter's permits before it starts; permits are handed out in release order.
+/// Cloning a `DropLimiter` yields another handle to the same permits.
+///
+/// A [drop timeout][crate::ArcyBuilder::drop_timeout] starts counting once the
+/// permit is acquired.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "tokio", doc = "```")]
+#[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// use arcy::{Arcy, AsyncDrop, DropLimiter};
+///
+/// static RUNNING: AtomicUsize = AtomicUsize::new(0);
+///
+/// struct Session;
+///
+/// impl AsyncDrop for Session {
+///     async fn async_drop(self) {
+///         assert!(RUNNING.fetch_add(1, Ordering::SeqCst) < 2);
+///         tokio::task::yield_now().await;
+///         RUNNING.fetch_sub(1, Ordering::SeqCst);
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let limiter = DropLimiter::new(2);
+/// let (sessions, handles): (Vec<_>, Vec<_>) = (0..10)
+///     .map(|_| Arcy::builder().limiter(&limiter).build(Session))
+///     .unzip();
+///
+/// drop(sessions);
+/// for handle in handles {
+///     assert!(handle.await.is_completed());
+/// }
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct DropLimiter {
+    inner: Arc<Mutex<State>>,
+}
+
+struct State {
+    available: usize,
+    next_id: u64,
+    // permits are handed over directly to the waiters, in order
+    waiters: VecDeque<(u64, Waker)>,
+}
+
+/// One of the permits of a [`DropLimiter`], given back when dropped.
+pub(crate) struct Permit(DropLimiter);
+
+static GLOBAL: RwLock<Option<DropLimiter>> = const_rwlock(None);
+// spares every `Arcy` created without a global limiter the read lock
+static HAS_GLOBAL: AtomicBool = AtomicBool::new(false);
+
+impl DropLimiter {
+    /// Creates a limiter letting at most `permits` async drops run at once.
+    pub fn new(permits: usize) -> Self {
+        let state = State {
+            available: permits,
+            next_id: 0,
+            waiters: VecDeque::new(),
+        };
+        Self {
+            inner: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Returns the number of async drops that could start right now.
+    pub fn available_permits(&self) -> usize {
+        self.inner.lock().available
+    }
+
+    /// Sets the limiter used by the values created from now on without a limiter
+    /// of their own, or removes it with `None`.
+    pub fn set_global(limiter: Option<Self>) {
+        let mut global = GLOBAL.write();
+        HAS_GLOBAL.store(limiter.is_some(), Ordering::Release);
+        *global = limiter;
+    }
+
+    /// Returns the limiter set with [`set_global`][Self::set_global], if any.
+    pub fn global() -> Option<Self> {
+        if !HAS_GLOBAL.load(Ordering::Acquire) {
+            return None;
+        }
+        GLOBAL.read().clone()
+    }
+
+    /// Waits for a permit.
+    pub(crate) fn acquire(&self) -> Acquire<'_> {
+        Acquire {
+            limiter: self,
+            id: None,
+            done: false,
+        }
+    }
+
+    fn release(&self) {
+        let waiter = {
+            let mut state = self.inner.lock();
+            match state.waiters.pop_front() {
+                Some((_, waker)) => waker,
+                None => {
+                    state.available += 1;
+                    return;
+                }
+            }
+        };
+        waiter.wake();
+    }
+}
+
+/// Future returned by [`DropLimiter::acquire`].
+pub(crate) struct Acquire<'a> {
+    limiter: &'a DropLimiter,
+    // set once queued
+    id: Option<u64>,
+    done: bool,
+}
+
+impl Future for Acquire<'_> {
+    type Output = Permit;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Permit> {
+        let mut state = self.limiter.inner.lock();
+        match self.id {
+            None if state.available > 0 && state.waiters.is_empty() => {
+                state.available -= 1;
+            }
+            None => {
+                let id = state.next_id;
+                state.next_id += 1;
+                state.waiters.push_back((id, cx.waker().clone()));
+                drop(state);
+                self.id = Some(id);
+                return Poll::Pending;
+            }
+            Some(id) => {
+                let queued = state.waiters.iter_mut().find(|(queued, _)| *queued == id);
+                // otherwise dequeued by a release, which handed its permit over
+                if let Some((_, waker)) = queued {
+                    if !waker.will_wake(cx.waker()) {
+                        *waker = cx.waker().clone();
+                    }
+                    return Poll::Pending;
+                }
+            }
+        }
+        drop(state);
+        self.done = true;
+        Poll::Ready(Permit(self.limiter.clone()))
+    }
+}
+
+impl Drop for Acquire<'_> {
+    fn drop(&mut self) {
+        let id = match (self.id, self.done) {
+            (Some(id), false) => id,
+            _ => return,
+        };
+        let mut state = self.limiter.inner.lock();
+        match state.waiters.iter().position(|(queued, _)| *queued == id) {
+            Some(position) => {
+                state.waiters.remove(position);
+            }
+            None => {
+                // a permit was handed over but never picked up: pass it on
+                drop(state);
+                self.limiter.release();
+            }
+        }
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
+
+impl fmt::Debug for DropLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.inner.lock();
+        f.debug_struct("DropLimiter")
+            .field("available", &state.available)
+            .field("waiting", &state.waiters.len())
+            .finish()
+    }
+}
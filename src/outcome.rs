@@ -0,0 +1,75 @@
+use std::any::Any;
+use std::convert::Infallible;
+
+/// How the async drop of a value ended, as reported by a [`DropHandle`][crate::DropHandle].
+///
+/// `E` is the [`TryAsyncDrop::Error`][crate::TryAsyncDrop::Error] of the value.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DropOutcome<E = Infallible> {
+    /// The async drop ran to completion.
+    Completed,
+    /// [`TryAsyncDrop::try_async_drop`][crate::TryAsyncDrop::try_async_drop] returned an error.
+    Failed(E),
+    /// The async drop panicked; the payload
+    /// is the one passed to [`std::panic::resume_unwind`].
+    ///
+    /// What else happens is decided by the [`PanicPolicy`][crate::PanicPolicy].
+    Panicked(Box<dyn Any + Send + 'static>),
+    /// The async drop was dropped before completing, e.g. because the executor
+    /// shut down, or won't happen, e.g. because it was
+    /// [aborted][crate::DropHandle::abort].
+    ///
+    /// A [`DropHandle`][crate::DropHandle] also resolves to this when the
+    /// outcome went elsewhere: when the value was taken back, or when the async
+    /// drop run by [`Arcy::release`][crate::Arcy::release] failed or panicked.
+    Cancelled,
+    /// The async drop didn't complete in time and was abandoned.
+    TimedOut,
+}
+
+impl<E> DropOutcome<E> {
+    /// Returns `true` if the async drop ran to completion.
+    pub fn is_completed(&self) -> bool {
+        matches!(self, Self::Completed)
+    }
+
+    /// Returns `true` if the async drop returned an error.
+    pub fn is_failed(&self) -> bool {
+        matches!(self, Self::Failed(_))
+    }
+
+    /// Returns `true` if the async drop panicked.
+    pub fn is_panicked(&self) -> bool {
+        matches!(self, Self::Panicked(_))
+    }
+
+    /// Returns `true` if the async drop was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, Self::Cancelled)
+    }
+
+    /// Returns `true` if the async drop timed out.
+    pub fn is_timed_out(&self) -> bool {
+        matches!(self, Self::TimedOut)
+    }
+
+    /// Returns the error the async drop failed with, if any.
+    pub fn err(self) -> Option<E> {
+        match self {
+            Self::Failed(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Maps the error the async drop failed with, if any.
+    pub fn map_err<F>(self, op: impl FnOnce(E) -> F) -> DropOutcome<F> {
+        match self {
+            Self::Completed => DropOutcome::Completed,
+            Self::Failed(err) => DropOutcome::Failed(op(err)),
+            Self::Panicked(payload) => DropOutcome::Panicked(payload),
+            Self::Cancelled => DropOutcome::Cancelled,
+            Self::TimedOut => DropOutcome::TimedOut,
+        }
+    }
+}
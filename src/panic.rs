@@ -0,0 +1,109 @@
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+/// What to do when [`AsyncDrop::async_drop`][crate::AsyncDrop::async_drop] panics.
+///
+/// The panic never takes down the executor: it's caught and, whatever the
+/// policy, reported as [`DropOutcome::Panicked`][crate::DropOutcome::Panicked]
+/// to the [`DropHandle`][crate::DropHandle]. The policy is set with
+/// [`ArcyBuilder::panic_policy`][crate::ArcyBuilder::panic_policy].
+///
+/// # Examples
+///
+#[cfg_attr(feature = "tokio", doc = "```")]
+#[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use std::sync::Arc;
+///
+/// use arcy::{Arcy, AsyncDrop, PanicPolicy};
+///
+/// struct Foo;
+///
+/// impl AsyncDrop for Foo {
+///     async fn async_drop(self) {
+///         panic!("cleanup failed");
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let failed = Arc::new(AtomicBool::new(false));
+/// let policy = PanicPolicy::callback({
+///     let failed = Arc::clone(&failed);
+///     move |_payload| failed.store(true, Ordering::SeqCst)
+/// });
+/// let (foo, last_foo) = Arcy::builder().panic_policy(policy).build(Foo);
+/// drop(foo);
+/// assert!(last_foo.await.is_panicked());
+/// assert!(failed.load(Ordering::SeqCst));
+/// # }
+/// ```
+#[derive(Clone, Default)]
+#[non_exhaustive]
+pub enum PanicPolicy {
+    /// Prints the panic message to stderr.
+    #[default]
+    Log,
+    /// Aborts the process.
+    Abort,
+    /// Passes the panic payload to a callback.
+    Callback(PanicCallback),
+    /// Resumes the panic in whoever awaits the [`DropHandle`][crate::DropHandle].
+    ///
+    /// If the handle is never awaited the panic goes unnoticed.
+    Resume,
+}
+
+/// Receives the payload of a panicked async drop, see [`PanicPolicy::Callback`].
+pub type PanicCallback = Arc<dyn Fn(&(dyn Any + Send)) + Send + Sync>;
+
+impl PanicPolicy {
+    /// Shorthand for [`PanicPolicy::Callback`].
+    pub fn callback(f: impl Fn(&(dyn Any + Send)) + Send + Sync + 'static) -> Self {
+        Self::Callback(Arc::new(f))
+    }
+
+    /// Handles the payload of a panicked async drop.
+    ///
+    /// `name` is the [name][crate::ArcyBuilder::name] of the value, if any.
+    pub(crate) fn handle(&self, payload: &(dyn Any + Send), name: Option<&str>) {
+        match self {
+            Self::Log => log(payload, name),
+            Self::Abort => {
+                log(payload, name);
+                std::process::abort();
+            }
+            Self::Callback(f) => f(payload),
+            Self::Resume => {}
+        }
+    }
+}
+
+fn log(payload: &(dyn Any + Send), name: Option<&str>) {
+    match name {
+        Some(name) => eprintln!("async drop of {} panicked: {}", name, message(payload)),
+        None => eprintln!("async drop panicked: {}", message(payload)),
+    }
+}
+
+fn message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
+impl fmt::Debug for PanicPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Log => write!(f, "Log"),
+            Self::Abort => write!(f, "Abort"),
+            Self::Callback(_) => write!(f, "Callback(..)"),
+            Self::Resume => write!(f, "Resume"),
+        }
+    }
+}
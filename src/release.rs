@@ -0,0 +1,462 @@
+//! What happens to a value once its last [`Arcy`][crate::Arcy] is gone.
+
+use std::any::Any;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::{pin, Pin};
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+
+use futures::future::{self, BoxFuture, Either};
+use futures::task::noop_waker_ref;
+use futures::FutureExt;
+use parking_lot::{MappedMutexGuard, Mutex, MutexGuard};
+
+use crate::dependency::Dependency;
+use crate::fallback::Unstarted;
+use crate::handle::{AnyError, DropState};
+use crate::tracker::Registration;
+use crate::{
+    DropExecutor, DropFuture, DropHandle, DropLimiter, DropOutcome, DropStrategy, DropTracker,
+    PanicPolicy, TryAsyncDrop,
+};
+
+/// Per allocation drop state, stored next to the value.
+///
+/// Nothing is spawned until the strong count drops to zero: only then a task
+/// running [`TryAsyncDrop::try_async_drop`] is spawned on the executor the value was
+/// created with.
+pub(crate) struct Header {
+    config: DropConfig,
+    completion: Mutex<Option<Completion>>,
+    // monomorphized for the concrete type of the value when the `Arcy` is created:
+    // `Drop` for `Arcy` can't ask for more bounds than the struct itself, and the
+    // `Arcy` may since have been unsized into a trait object.
+    spawn: unsafe fn(&Self, *mut ()),
+}
+
+/// How the async drop of a value is run, as set up by the
+/// [`ArcyBuilder`][crate::ArcyBuilder].
+///
+/// Copies made by [`Arcy::make_mut`][crate::Arcy::make_mut] are dropped the same way.
+#[derive(Clone)]
+pub(crate) struct DropConfig {
+    pub(crate) executor: Executor,
+    pub(crate) strategy: DropStrategy,
+    // kept out of the allocation of the value, as most values don't set any
+    pub(crate) options: Option<Arc<DropOptions>>,
+}
+
+/// The rest of a [`DropConfig`].
+pub(crate) struct DropOptions {
+    pub(crate) name: Option<Arc<str>>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) panic_policy: PanicPolicy,
+    pub(crate) tracker: Option<DropTracker>,
+    pub(crate) limiter: Option<DropLimiter>,
+    pub(crate) fallback: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+static DEFAULT_OPTIONS: DropOptions = DropOptions {
+    name: None,
+    timeout: None,
+    panic_policy: PanicPolicy::Log,
+    tracker: None,
+    limiter: None,
+    fallback: None,
+};
+
+/// The executor of a [`DropConfig`].
+///
+/// The runtime captured by default is kept as is: boxing its handle would cost
+/// an allocation per value.
+#[derive(Clone)]
+pub(crate) enum Executor {
+    #[cfg(feature = "tokio")]
+    Runtime(tokio::runtime::Handle),
+    Custom(Arc<dyn DropExecutor>),
+}
+
+/// Everyone waiting for the async drop to be over.
+///
+/// Dropping it without calling [`complete`][Self::complete] reports the drop as
+/// cancelled. It's moved into the async drop, so it's kept small: what only some
+/// values need lives in a separate allocation.
+pub(crate) struct Completion {
+    state: Arc<DropState>,
+    options: Option<Arc<DropOptions>>,
+    extra: Option<Box<Extra>>,
+}
+
+#[derive(Default)]
+struct Extra {
+    on_timeout: Option<Box<dyn FnOnce() + Send>>,
+    // released once the async drop is over, before the tracker hears of it
+    dependencies: Vec<Box<dyn Dependency>>,
+    _registration: Option<Registration>,
+}
+
+/// The async drop of a value, cancelled once its [`DropHandle`] aborts it, the
+/// next time it's polled.
+struct Abortable<F> {
+    drop: F,
+    completion: Option<Completion>,
+}
+
+impl Header {
+    /// Creates the header of a value of type `T`.
+    pub(crate) fn new<T>(
+        config: DropConfig,
+        on_timeout: Option<Box<dyn FnOnce() + Send>>,
+    ) -> (Self, DropHandle<T::Error>)
+    where
+        T: TryAsyncDrop + Send + 'static,
+    {
+        let completion = Completion::new(&config, on_timeout);
+        let handle = completion.handle().expect("no other handle");
+        let header = Self {
+            config,
+            completion: Mutex::new(Some(completion)),
+            spawn: Self::spawn::<T>,
+        };
+        (header, handle)
+    }
+
+    /// A header for a copy of the value, dropped the same way but whose outcome
+    /// isn't reported to anyone.
+    ///
+    /// The copy depends on the same values.
+    pub(crate) fn fork(&self) -> Self {
+        let config = self.config.clone();
+        let mut completion = Completion::new(&config, None);
+        if let Some(dependencies) = self.dependencies() {
+            let copies = dependencies.iter().map(|dependency| dependency.clone_box());
+            completion.dependencies().extend(copies);
+        }
+        self.with_completion(config, Some(completion))
+    }
+
+    /// A header for the value moving to a new allocation, taking over whoever
+    /// waits for its drop.
+    pub(crate) fn relocate(&self) -> Self {
+        let completion = self.completion.lock().take();
+        self.with_completion(self.config.clone(), completion)
+    }
+
+    fn with_completion(&self, config: DropConfig, completion: Option<Completion>) -> Self {
+        Self {
+            config,
+            completion: Mutex::new(completion),
+            spawn: self.spawn,
+        }
+    }
+
+    /// Resolves once the async drop is over, or won't happen.
+    pub(crate) fn dropped(&self) -> DropFuture {
+        let completion = self.completion.lock();
+        // only taken once the last `Arcy` is gone
+        completion.as_ref().expect("live value").state.dropped()
+    }
+
+    /// Runs the async drop of `value` as configured, up to its outcome, unless
+    /// aborted through the [`DropHandle`] of `completion`, handed back with it.
+    fn run<T>(
+        &self,
+        value: Unstarted<T>,
+        completion: Option<Completion>,
+    ) -> impl Future<Output = (DropOutcome<T::Error>, Option<Completion>)> + Send
+    where
+        T: TryAsyncDrop + Send + 'static,
+    {
+        let options = self.config.options();
+        let drop = match (&options.limiter, options.timeout) {
+            // the future is moved around and allocated on spawn: boxing the part
+            // only some values need keeps it small for the others
+            (None, None) => Either::Left(async move { try_async_drop(value.start()).await }),
+            (limiter, timeout) => {
+                let limiter = limiter.clone();
+                // the executor is only needed for its timer
+                let sleeper = self.config.executor.clone();
+                Either::Right(Box::pin(async move {
+                    let permit = match &limiter {
+                        Some(limiter) => Some(limiter.acquire().await),
+                        None => None,
+                    };
+                    let value = value.start();
+                    let deadline = timeout.map(|timeout| sleeper.sleep(timeout));
+                    let drop = pin!(try_async_drop(value));
+                    let outcome = match deadline {
+                        Some(deadline) => match future::select(drop, deadline).await {
+                            Either::Left((outcome, _)) => outcome,
+                            // the async drop is cancelled by dropping it
+                            Either::Right(_) => DropOutcome::TimedOut,
+                        },
+                        None => drop.await,
+                    };
+                    std::mem::drop(permit);
+                    outcome
+                }))
+            }
+        };
+        Abortable { drop, completion }
+    }
+
+    /// # Safety
+    ///
+    /// `value` must point to a `T` that can be moved out.
+    unsafe fn spawn<T>(&self, value: *mut ())
+    where
+        T: TryAsyncDrop + Send + 'static,
+    {
+        let options = self.config.options();
+        let value = Unstarted::new(value.cast::<T>().read(), options.fallback.clone());
+        let completion = self.completion.lock().take();
+        let drop = self.run(value, completion);
+        let executor = &self.config.executor;
+        let future = drop.map(|(outcome, completion)| {
+            if let Some(completion) = completion {
+                completion.complete(outcome.map_err(|err| Box::new(err) as AnyError));
+            }
+        });
+        let mut future: BoxFuture<'static, ()> = Box::pin(future);
+        match self.config.strategy {
+            DropStrategy::Spawn => {}
+            DropStrategy::Inline => {
+                // the executor's task polls it again, registering its own waker
+                let mut cx = Context::from_waker(noop_waker_ref());
+                // panics of the async drop are reported as such: any other one,
+                // e.g. of the panic policy, cancels it rather than unwinding out
+                // of `Drop`
+                match panic::catch_unwind(AssertUnwindSafe(|| future.poll_unpin(&mut cx))) {
+                    Ok(Poll::Pending) => {}
+                    Ok(Poll::Ready(())) | Err(_) => return,
+                }
+            }
+            DropStrategy::BlockOn => {
+                // the async drop catches its own panics: any other one cancels it,
+                // rather than unwinding out of `Drop`
+                match panic::catch_unwind(AssertUnwindSafe(|| executor.block_on(future))) {
+                    Ok(Err(unblocked)) => future = unblocked,
+                    Ok(Ok(())) | Err(_) => return,
+                }
+            }
+        }
+        // if the executor can't run it (or later drops it, e.g. because it's shutting
+        // down), the future goes away taking the value and the completion with it:
+        // unless the async drop has started, the value goes to the sync fallback
+        let _ = match &options.name {
+            Some(name) => executor.spawn_named(name, future),
+            None => executor.spawn(future),
+        };
+    }
+
+    /// Spawns the async drop of a value whose last strong reference is gone.
+    ///
+    /// # Safety
+    ///
+    /// `value` must point to the value this header was created for, which is moved
+    /// out: the caller must have released its last strong reference.
+    pub(crate) unsafe fn release(&self, value: *mut ()) {
+        (self.spawn)(self, value)
+    }
+
+    /// Runs the async drop of a value whose last strong reference was released by
+    /// [`Arcy::release`][crate::Arcy::release], on the caller's task.
+    ///
+    /// The error or panic payload of the outcome goes to the caller only: whoever
+    /// waits for the drop sees it as cancelled then.
+    pub(crate) fn release_inline<T>(
+        &self,
+        value: T,
+    ) -> impl Future<Output = DropOutcome<T::Error>> + Send
+    where
+        T: TryAsyncDrop + Send + 'static,
+    {
+        let completion = self.completion.lock().take();
+        let options = self.config.options();
+        let value = Unstarted::new(value, options.fallback.clone());
+        let resume_panics = matches!(options.panic_policy, PanicPolicy::Resume);
+        let drop = self.run(value, completion);
+        async move {
+            let (outcome, completion) = drop.await;
+            if let Some(mut completion) = completion {
+                completion.settle(&outcome);
+                completion.report(match outcome {
+                    DropOutcome::Completed => DropOutcome::Completed,
+                    DropOutcome::TimedOut => DropOutcome::TimedOut,
+                    _ => DropOutcome::Cancelled,
+                });
+            }
+            match outcome {
+                DropOutcome::Panicked(payload) if resume_panics => panic::resume_unwind(payload),
+                outcome => outcome,
+            }
+        }
+    }
+
+    /// Returns a new handle to the async drop, unless it's already been spawned or
+    /// there is another handle.
+    pub(crate) fn handle<E>(&self) -> Option<DropHandle<E>> {
+        self.completion.lock().as_ref()?.handle()
+    }
+
+    /// Gives up on the async drop of a value that was taken back by its owner.
+    ///
+    /// Whoever waits for the drop sees it as cancelled.
+    pub(crate) fn cancel(&self) {
+        let completion = self.completion.lock().take();
+        // once unlocked: releasing the dependencies may start their async drop
+        drop(completion);
+    }
+
+    /// Returns the values that must outlive the async drop, unless it's already
+    /// been spawned or cancelled.
+    pub(crate) fn dependencies(&self) -> Option<MappedMutexGuard<'_, Vec<Box<dyn Dependency>>>> {
+        MutexGuard::try_map(self.completion.lock(), |completion| {
+            completion.as_mut().map(Completion::dependencies)
+        })
+        .ok()
+    }
+}
+
+impl Completion {
+    fn new(config: &DropConfig, on_timeout: Option<Box<dyn FnOnce() + Send>>) -> Self {
+        let registration = config.options().tracker.as_ref().map(DropTracker::register);
+        let extra = match (on_timeout, registration) {
+            (None, None) => None,
+            (on_timeout, registration) => Some(Box::new(Extra {
+                on_timeout,
+                dependencies: Vec::new(),
+                _registration: registration,
+            })),
+        };
+        Self {
+            state: Arc::default(),
+            options: config.options.clone(),
+            extra,
+        }
+    }
+
+    fn handle<E>(&self) -> Option<DropHandle<E>> {
+        let resume_panics = matches!(self.options().panic_policy, PanicPolicy::Resume);
+        self.state.handle(resume_panics)
+    }
+
+    fn options(&self) -> &DropOptions {
+        self.options.as_deref().unwrap_or(&DEFAULT_OPTIONS)
+    }
+
+    fn dependencies(&mut self) -> &mut Vec<Box<dyn Dependency>> {
+        &mut self.extra.get_or_insert_with(Box::default).dependencies
+    }
+
+    fn complete(mut self, outcome: DropOutcome<AnyError>) {
+        self.settle(&outcome);
+        self.report(outcome);
+    }
+
+    /// Reports `outcome` to whoever waits for the drop, once it's been settled.
+    fn report(mut self, outcome: DropOutcome<AnyError>) {
+        // the dependencies may start their own async drop now
+        drop(self.extra.take());
+        self.state.finish(outcome);
+    }
+
+    /// Does what `outcome` calls for, but for reporting it.
+    fn settle<E>(&mut self, outcome: &DropOutcome<E>) {
+        match (outcome, &mut self.extra) {
+            (DropOutcome::Panicked(payload), _) => {
+                let options = self.options();
+                options
+                    .panic_policy
+                    .handle(&**payload, options.name.as_deref())
+            }
+            (DropOutcome::TimedOut, Some(extra)) => {
+                if let Some(on_timeout) = extra.on_timeout.take() {
+                    on_timeout();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Drop for Completion {
+    fn drop(&mut self) {
+        drop(self.extra.take());
+        // unless completed already
+        self.state.finish(DropOutcome::Cancelled);
+    }
+}
+
+impl DropConfig {
+    pub(crate) fn options(&self) -> &DropOptions {
+        self.options.as_deref().unwrap_or(&DEFAULT_OPTIONS)
+    }
+}
+
+impl DropOptions {
+    /// Whether these are the options of a value that didn't set any.
+    pub(crate) fn is_default(&self) -> bool {
+        self.name.is_none()
+            && self.timeout.is_none()
+            && matches!(self.panic_policy, PanicPolicy::Log)
+            && self.tracker.is_none()
+            && self.limiter.is_none()
+            && self.fallback.is_none()
+    }
+}
+
+impl<F, E> Future for Abortable<F>
+where
+    F: Future<Output = DropOutcome<E>>,
+{
+    type Output = (DropOutcome<E>, Option<Completion>);
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `drop` is pinned along with `self` and never moved out, while
+        // nothing pins the completion
+        let this = unsafe { self.get_unchecked_mut() };
+        let drop = unsafe { Pin::new_unchecked(&mut this.drop) };
+        let aborted = match &this.completion {
+            Some(completion) => completion.state.is_aborted(),
+            None => false,
+        };
+        // checked first: an async drop aborted before it starts never does
+        let outcome = match aborted {
+            true => DropOutcome::Cancelled,
+            // not only the value may panic, but also what runs it: e.g. the timer
+            // of a timeout, or a limiter
+            false => match panic::catch_unwind(AssertUnwindSafe(|| drop.poll(cx))) {
+                Ok(poll) => ready!(poll),
+                Err(payload) => DropOutcome::Panicked(payload),
+            },
+        };
+        Poll::Ready((outcome, this.completion.take()))
+    }
+}
+
+/// Async drops `value`, calling [`TryAsyncDrop::try_async_drop`] on first poll:
+/// its panics are caught along with those of the future it returns.
+async fn try_async_drop<T>(value: T) -> DropOutcome<T::Error>
+where
+    T: TryAsyncDrop,
+{
+    match value.try_async_drop().await {
+        Ok(()) => DropOutcome::Completed,
+        Err(err) => DropOutcome::Failed(err),
+    }
+}
+
+impl std::ops::Deref for Executor {
+    type Target = dyn DropExecutor;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            #[cfg(feature = "tokio")]
+            Self::Runtime(runtime) => runtime,
+            Self::Custom(executor) => &**executor,
+        }
+    }
+}
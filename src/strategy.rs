@@ -0,0 +1,37 @@
+/// Where the async drop of a value runs.
+///
+/// Set with [`ArcyBuilder::strategy`][crate::ArcyBuilder::strategy].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DropStrategy {
+    /// Spawn the async drop on the executor.
+    #[default]
+    Spawn,
+    /// Poll the async drop once on the thread releasing the last reference, and
+    /// only spawn it on the executor if it doesn't complete right away.
+    ///
+    /// This saves a task for the drops that don't actually wait on anything. The
+    /// first poll runs wherever the last reference is dropped, possibly outside of
+    /// any runtime: an async drop relying on its runtime must not use this. If it
+    /// panics, e.g. because it does, the handle resolves to
+    /// [`DropOutcome::Panicked`][crate::DropOutcome::Panicked] and releasing the
+    /// last reference doesn't panic.
+    Inline,
+    /// Run the async drop to completion on the thread releasing the last
+    /// reference, blocking it, when that thread doesn't run an executor: e.g. a
+    /// `rayon` worker or an FFI callback, where nothing could be spawned.
+    ///
+    /// What blocking means is up to the executor, see [`DropExecutor::block_on`][crate::DropExecutor::block_on]:
+    /// a Tokio
+    #[cfg_attr(feature = "tokio", doc = "[`Handle`][tokio::runtime::Handle]")]
+    #[cfg_attr(not(feature = "tokio"), doc = "`Handle`")]
+    /// blocks on its runtime, which must be driven by other threads for timers and IO to make progress. On a thread
+    /// running an executor, a Tokio runtime or a `futures` one such as
+    /// `ThreadPool`, the async drop is spawned as with [`Spawn`][Self::Spawn].
+    ///
+    /// Releasing the last reference never panics: panics in the async drop are
+    /// handled by the [`PanicPolicy`][crate::PanicPolicy] as usual, and any other panic while blocking
+    /// cancels the async drop. The releasing thread must not hold anything the
+    /// async drop waits for, or it deadlocks.
+    BlockOn,
+}
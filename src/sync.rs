@@ -0,0 +1,41 @@
+//! Synchronization primitives used by the reference counting core.
+//!
+//! When built with `--cfg loom` these are swapped for their [loom] counterparts so
+//! that the hand-off between the last [`Arcy`][crate::Arcy] and the drop task can be
+//! model checked.
+//!
+//! [loom]: https://docs.rs/loom
+
+#[cfg(loom)]
+pub(crate) use loom::{
+    cell::UnsafeCell,
+    sync::atomic::{fence, AtomicUsize},
+};
+
+#[cfg(not(loom))]
+pub(crate) use std::sync::atomic::{fence, AtomicUsize};
+
+/// Mirrors the closure based API of `loom::cell::UnsafeCell`.
+#[cfg(not(loom))]
+#[derive(Debug)]
+pub(crate) struct UnsafeCell<T: ?Sized>(std::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    pub(crate) const fn new(data: T) -> Self {
+        Self(std::cell::UnsafeCell::new(data))
+    }
+}
+
+#[cfg(not(loom))]
+impl<T: ?Sized> UnsafeCell<T> {
+    #[inline(always)]
+    pub(crate) fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        f(self.0.get())
+    }
+
+    #[inline(always)]
+    pub(crate) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
@@ -0,0 +1,113 @@
+//! The timer behind the default [`DropExecutor::sleep`][crate::DropExecutor::sleep]:
+//! one thread, started on first use, keeping track of every deadline.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Once};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures::task::AtomicWaker;
+use parking_lot::{const_mutex, Condvar, Mutex};
+
+struct Timer {
+    // the sleeps by deadline, ties broken by creation order
+    sleeps: Mutex<Sleeps>,
+    changed: Condvar,
+}
+
+struct Sleeps {
+    next_id: u64,
+    pending: BTreeMap<(Instant, u64), Arc<Wakeup>>,
+}
+
+struct Wakeup {
+    fired: AtomicBool,
+    waker: AtomicWaker,
+}
+
+/// Future returned by [`sleep`].
+pub(crate) struct Sleep {
+    key: (Instant, u64),
+    wakeup: Arc<Wakeup>,
+}
+
+static TIMER: Timer = Timer {
+    sleeps: const_mutex(Sleeps {
+        next_id: 0,
+        pending: BTreeMap::new(),
+    }),
+    changed: Condvar::new(),
+};
+
+static START: Once = Once::new();
+
+/// Completes after `duration`.
+pub(crate) fn sleep(duration: Duration) -> Sleep {
+    START.call_once(|| {
+        std::thread::Builder::new()
+            .name("arcy-timer".to_owned())
+            .spawn(|| TIMER.run())
+            .expect("failed to spawn the timer thread");
+    });
+    let timer = &TIMER;
+    let wakeup = Arc::new(Wakeup {
+        fired: AtomicBool::new(false),
+        waker: AtomicWaker::new(),
+    });
+    let mut sleeps = timer.sleeps.lock();
+    let key = (Instant::now() + duration, sleeps.next_id);
+    sleeps.next_id += 1;
+    sleeps.pending.insert(key, Arc::clone(&wakeup));
+    // the timer thread waits for the earliest deadline only
+    if sleeps.pending.keys().next() == Some(&key) {
+        timer.changed.notify_one();
+    }
+    Sleep { key, wakeup }
+}
+
+impl Timer {
+    fn run(&self) -> ! {
+        let mut sleeps = self.sleeps.lock();
+        loop {
+            let now = Instant::now();
+            while let Some(entry) = sleeps.pending.first_entry() {
+                if entry.key().0 > now {
+                    break;
+                }
+                let wakeup = entry.remove();
+                wakeup.fired.store(true, Ordering::Release);
+                wakeup.waker.wake();
+            }
+            match sleeps.pending.keys().next() {
+                Some(&(deadline, _)) => {
+                    self.changed.wait_until(&mut sleeps, deadline);
+                }
+                None => self.changed.wait(&mut sleeps),
+            }
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.wakeup.waker.register(cx.waker());
+        if self.wakeup.fired.load(Ordering::Acquire) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if !self.wakeup.fired.load(Ordering::Acquire) {
+            TIMER.sleeps.lock().pending.remove(&self.key);
+        }
+    }
+}
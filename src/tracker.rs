@@ -0,0 +1,123 @@
+use std::fmt;
+use std::future::poll_fn;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Poll, Waker};
+use std::time::Duration;
+
+use futures::future::{self, Either};
+use parking_lot::Mutex;
+
+use crate::{timer, Elapsed};
+
+/// Keeps count of the async drops of a group of [`Arcy`][crate::Arcy] values,
+/// so that they can all be awaited at once, e.g. on service shutdown.
+///
+/// Values are registered with [`ArcyBuilder::tracker`][crate::ArcyBuilder::tracker]
+/// and count as pending from the moment they're created until their
+/// [`AsyncDrop::async_drop`][crate::AsyncDrop::async_drop] completes (or is
+/// cancelled). Cloning a `DropTracker` yields another handle to the same group.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "tokio", doc = "```")]
+#[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+/// # use arcy::{Arcy, AsyncDrop, DropTracker};
+/// # struct Conn;
+/// # impl AsyncDrop for Conn {
+/// #     async fn async_drop(self) {}
+/// # }
+/// # #[tokio::main]
+/// # async fn main() {
+/// let tracker = DropTracker::new();
+/// let conns: Vec<_> = (0..3)
+///     .map(|_| Arcy::builder().tracker(&tracker).build(Conn).0)
+///     .collect();
+/// assert_eq!(tracker.pending(), 3);
+///
+/// drop(conns);
+/// tracker.drain().await;
+/// assert_eq!(tracker.pending(), 0);
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct DropTracker {
+    inner: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    pending: usize,
+    drainers: Vec<Waker>,
+}
+
+/// Counts as one pending drop until dropped.
+pub(crate) struct Registration(DropTracker);
+
+impl DropTracker {
+    /// Creates an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of tracked values whose async drop hasn't completed yet.
+    pub fn pending(&self) -> usize {
+        self.inner.lock().pending
+    }
+
+    /// Waits until every tracked value has been async dropped.
+    ///
+    /// Values registered while draining are waited for as well.
+    pub async fn drain(&self) {
+        poll_fn(|cx| {
+            let mut state = self.inner.lock();
+            if state.pending == 0 {
+                return Poll::Ready(());
+            }
+            if !state.drainers.iter().any(|w| w.will_wake(cx.waker())) {
+                state.drainers.push(cx.waker().clone());
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Like [`drain`][Self::drain], but gives up after `timeout`.
+    ///
+    /// The deadline is kept by the timer thread shared with the drop timeouts,
+    /// so this works on any executor.
+    pub async fn drain_timeout(&self, timeout: Duration) -> Result<(), Elapsed> {
+        let drain = pin!(self.drain());
+        match future::select(drain, timer::sleep(timeout)).await {
+            Either::Left(_) => Ok(()),
+            Either::Right(_) => Err(Elapsed(())),
+        }
+    }
+
+    pub(crate) fn register(&self) -> Registration {
+        self.inner.lock().pending += 1;
+        Registration(self.clone())
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let drainers = {
+            let mut state = self.0.inner.lock();
+            state.pending -= 1;
+            if state.pending != 0 {
+                return;
+            }
+            std::mem::take(&mut state.drainers)
+        };
+        drainers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl fmt::Debug for DropTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DropTracker")
+            .field("pending", &self.pending())
+            .finish()
+    }
+}
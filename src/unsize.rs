@@ -0,0 +1,80 @@
+//! Conversions from `Arcy<T>` to `Arcy<dyn Trait>`.
+
+use std::sync::Arc;
+
+use crate::inner::ArcyInner;
+use crate::release::Header;
+use crate::Arcy;
+
+/// Converts an `Arcy<T>` into an `Arcy<U>` where `T` unsizes to `U`, usually
+/// an `Arcy<dyn Trait>` for some trait `T` implements: `unsize!(arcy, dyn Trait)`.
+///
+/// The async drop is tied to the concrete type the `Arcy` was created with, so
+/// the trait object doesn't need to be [`AsyncDrop`][crate::AsyncDrop] itself:
+/// once the last pointer is gone, `T`'s async drop runs as usual. `Arcy` can't
+/// implement [`CoerceUnsized`][std::ops::CoerceUnsized] on stable Rust, hence
+/// the macro.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "tokio", doc = "```")]
+#[cfg_attr(not(feature = "tokio"), doc = "```ignore")]
+/// use arcy::{Arcy, AsyncDrop};
+///
+/// trait Connection: Send + Sync {
+///     fn name(&self) -> &str;
+/// }
+///
+/// struct Postgres;
+///
+/// impl Connection for Postgres {
+///     fn name(&self) -> &str {
+///         "postgres"
+///     }
+/// }
+///
+/// impl AsyncDrop for Postgres {
+///     async fn async_drop(self) {
+///         // send a terminate message
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (pg, last_pg) = Arcy::new(Postgres);
+/// let conns: Vec<Arcy<dyn Connection>> = vec![arcy::unsize!(pg, dyn Connection)];
+/// assert_eq!(conns[0].name(), "postgres");
+///
+/// drop(conns);
+/// assert!(last_pg.await.is_completed());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! unsize {
+    ($arcy:expr, $target:ty) => {{
+        let arcy = $arcy;
+        // the closure does nothing but an unsizing coercion
+        unsafe { $crate::Arcy::__unsize::<$target, _>(arcy, |ptr| ptr) }
+    }};
+}
+
+impl<T: ?Sized> Arcy<T> {
+    /// Implementation detail of [`unsize!`].
+    ///
+    /// # Safety
+    ///
+    /// `f` must return its argument, coerced to `*const U`.
+    #[doc(hidden)]
+    pub unsafe fn __unsize<U, F>(this: Self, f: F) -> Arcy<U>
+    where
+        U: ?Sized,
+        F: FnOnce(*const T) -> *const U,
+    {
+        let inner = Arc::into_raw(Self::into_raw_inner(this));
+        // only the pointer metadata changes: the value is the tail of the allocation
+        let inner = f(inner as *const T) as *const ArcyInner<U, Header>;
+        Arcy {
+            inner: Arc::from_raw(inner),
+        }
+    }
+}
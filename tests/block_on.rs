@@ -0,0 +1,160 @@
+//! Async drops run to completion by the thread releasing the last reference.
+
+#![cfg(feature = "tokio")]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use arcy::{executor, Arcy, AsyncDrop, DropExecutor, DropHandle, DropStrategy};
+use futures::executor::block_on;
+use futures::future::BoxFuture;
+use tokio::runtime::{Builder, Runtime};
+
+struct Conn {
+    closed: Arc<AtomicBool>,
+    // waits on a Tokio timer first
+    sleep: bool,
+    panic: bool,
+}
+
+impl AsyncDrop for Conn {
+    async fn async_drop(self) {
+        if self.sleep {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        if self.panic {
+            panic!("can't close");
+        }
+        self.closed.store(true, Ordering::SeqCst);
+    }
+}
+
+fn runtime() -> Runtime {
+    Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+/// Creates a connection blocked on by `executor` once released.
+fn conn(
+    executor: impl DropExecutor,
+    sleep: bool,
+    panic: bool,
+) -> (Arcy<Conn>, DropHandle, Arc<AtomicBool>) {
+    let closed = Arc::new(AtomicBool::new(false));
+    let value = Conn {
+        closed: Arc::clone(&closed),
+        sleep,
+        panic,
+    };
+    let (conn, handle) = Arcy::builder()
+        .executor(executor)
+        .strategy(DropStrategy::BlockOn)
+        .build(value);
+    (conn, handle, closed)
+}
+
+#[test]
+fn blocks_outside_of_a_runtime() {
+    let runtime = runtime();
+    let (conn, handle, closed) = conn(runtime.handle().clone(), true, false);
+    std::thread::spawn(move || drop(conn)).join().unwrap();
+    assert!(closed.load(Ordering::SeqCst));
+    assert!(block_on(handle).is_completed());
+}
+
+#[test]
+fn blocks_with_a_timeout() {
+    let runtime = runtime();
+    let closed = Arc::new(AtomicBool::new(false));
+    let value = Conn {
+        closed: Arc::clone(&closed),
+        sleep: true,
+        panic: false,
+    };
+    let (conn, handle) = Arcy::builder()
+        .executor(runtime.handle().clone())
+        .strategy(DropStrategy::BlockOn)
+        .drop_timeout(Duration::from_millis(1))
+        .build(value);
+    drop(conn);
+    assert!(!closed.load(Ordering::SeqCst));
+    assert!(block_on(handle).is_timed_out());
+}
+
+#[test]
+fn spawns_within_a_runtime() {
+    let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    runtime.block_on(async {
+        let (conn, handle, closed) = conn(tokio::runtime::Handle::current(), false, false);
+        drop(conn);
+        // the task didn't get to run yet
+        assert!(!closed.load(Ordering::SeqCst));
+        assert!(handle.await.is_completed());
+        assert!(closed.load(Ordering::SeqCst));
+    });
+}
+
+#[test]
+fn blocks_without_a_runtime() {
+    let (conn, handle, closed) = conn(executor::Tokio, false, false);
+    drop(conn);
+    assert!(closed.load(Ordering::SeqCst));
+    assert!(block_on(handle).is_completed());
+}
+
+#[test]
+fn panics_stay_in_the_async_drop() {
+    let runtime = runtime();
+    let (conn, handle, closed) = conn(runtime.handle().clone(), false, true);
+    drop(conn);
+    assert!(!closed.load(Ordering::SeqCst));
+    assert!(block_on(handle).is_panicked());
+}
+
+#[test]
+fn no_timer_without_a_runtime() {
+    // the async drop can't use Tokio's timer with nothing to block on
+    let (conn, handle, closed) = conn(executor::Tokio, true, false);
+    drop(conn);
+    assert!(!closed.load(Ordering::SeqCst));
+    assert!(block_on(handle).is_panicked());
+}
+
+struct Broken;
+
+impl DropExecutor for Broken {
+    fn spawn(&self, future: BoxFuture<'static, ()>) -> Result<(), BoxFuture<'static, ()>> {
+        Err(future)
+    }
+
+    fn block_on(&self, _: BoxFuture<'static, ()>) -> Result<(), BoxFuture<'static, ()>> {
+        panic!("broken executor");
+    }
+}
+
+#[test]
+fn executor_panics_cancel_the_async_drop() {
+    let (conn, handle, closed) = conn(Broken, false, false);
+    drop(conn);
+    assert!(!closed.load(Ordering::SeqCst));
+    assert!(block_on(handle).is_cancelled());
+}
+
+#[cfg(feature = "thread-pool")]
+#[test]
+fn spawns_within_a_thread_pool() {
+    let pool = futures::executor::ThreadPool::new().unwrap();
+    let (conn, handle, closed) = conn(pool.clone(), false, false);
+    let (released, released_rx) = futures::channel::oneshot::channel();
+    pool.spawn_ok(async move {
+        drop(conn);
+        let _ = released.send(());
+    });
+    block_on(released_rx).unwrap();
+    assert!(block_on(handle).is_completed());
+    assert!(closed.load(Ordering::SeqCst));
+}
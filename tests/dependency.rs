@@ -0,0 +1,109 @@
+//! Ordering of the async drops declared with `Arcy::depends_on`.
+
+#![cfg(feature = "tokio")]
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use arcy::{Arcy, AsyncDrop};
+
+/// Logs the start and the end of its async drop, taking some time in between.
+#[derive(Clone)]
+struct Conn(&'static str, Arc<Mutex<Vec<String>>>);
+
+impl AsyncDrop for Conn {
+    async fn async_drop(self) {
+        self.1.lock().unwrap().push(format!("start {}", self.0));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        self.1.lock().unwrap().push(format!("end {}", self.0));
+    }
+}
+
+#[tokio::test]
+async fn dependency_dropped_after_dependent() {
+    let log = Arc::default();
+    let (pool, last_pool) = Arcy::new(Conn("pool", Arc::clone(&log)));
+    let (session, last_session) = Arcy::new(Conn("session", Arc::clone(&log)));
+    Arcy::depends_on(&session, &pool).unwrap();
+
+    // released first, but held by the session
+    drop(pool);
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(log.lock().unwrap().is_empty());
+
+    drop(session);
+    assert!(last_session.await.is_completed());
+    assert!(last_pool.await.is_completed());
+    let expected = ["start session", "end session", "start pool", "end pool"];
+    assert_eq!(*log.lock().unwrap(), expected);
+}
+
+#[tokio::test]
+async fn chains_in_order() {
+    let log = Arc::default();
+    let (a, last_a) = Arcy::new(Conn("a", Arc::clone(&log)));
+    let (b, last_b) = Arcy::new(Conn("b", Arc::clone(&log)));
+    let (c, last_c) = Arcy::new(Conn("c", Arc::clone(&log)));
+    Arcy::depends_on(&a, &b).unwrap();
+    Arcy::depends_on(&b, &c).unwrap();
+
+    drop((c, b, a));
+    for handle in [last_a, last_b, last_c] {
+        assert!(handle.await.is_completed());
+    }
+    let expected = ["start a", "end a", "start b", "end b", "start c", "end c"];
+    assert_eq!(*log.lock().unwrap(), expected);
+}
+
+#[tokio::test]
+async fn self_dependency_is_a_cycle() {
+    let (a, _) = Arcy::new(Conn("a", Arc::default()));
+    assert!(Arcy::depends_on(&a, &a).is_err());
+}
+
+#[tokio::test]
+async fn transitive_cycle() {
+    let log = Arc::default();
+    let (a, _) = Arcy::new(Conn("a", Arc::clone(&log)));
+    let (b, _) = Arcy::new(Conn("b", Arc::clone(&log)));
+    let (c, _) = Arcy::new(Conn("c", Arc::clone(&log)));
+    Arcy::depends_on(&a, &b).unwrap();
+    Arcy::depends_on(&b, &c).unwrap();
+    assert!(Arcy::depends_on(&c, &a).is_err());
+    // no harm done: `c` can depend on something else
+    let (d, _) = Arcy::new(Conn("d", Arc::clone(&log)));
+    Arcy::depends_on(&c, &d).unwrap();
+}
+
+#[tokio::test]
+async fn declared_once() {
+    let (a, _) = Arcy::new(Conn("a", Arc::default()));
+    let (b, _) = Arcy::new(Conn("b", Arc::default()));
+    Arcy::depends_on(&a, &b).unwrap();
+    Arcy::depends_on(&a, &b).unwrap();
+    assert_eq!(Arcy::strong_count(&b), 2);
+}
+
+#[tokio::test]
+async fn copies_keep_the_dependencies() {
+    let log = Arc::default();
+    let (pool, last_pool) = Arcy::new(Conn("pool", Arc::clone(&log)));
+    let (mut session, last_session) = Arcy::new(Conn("session", Arc::clone(&log)));
+    Arcy::depends_on(&session, &pool).unwrap();
+    let other_session = session.clone();
+    Arcy::make_mut(&mut session).0 = "copy";
+
+    drop((pool, other_session));
+    assert!(last_session.await.is_completed());
+    // still held by the copy
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(!last_pool.is_finished());
+
+    drop(session);
+    assert!(last_pool.await.is_completed());
+    let log = log.lock().unwrap();
+    assert_eq!(
+        log[log.len() - 4..],
+        ["start copy", "end copy", "start pool", "end pool"]
+    );
+}
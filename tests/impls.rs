@@ -0,0 +1,96 @@
+//! Async drop of containers, and of the values wrapped to be async dropped.
+
+#![cfg(feature = "tokio")]
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use arcy::{Arcy, AsyncDrop, DropHandle, Sequential, SyncDrop};
+use tokio::sync::Barrier;
+
+/// Only completes its async drop once as many others are running theirs.
+struct Conn(Arc<Barrier>);
+
+impl AsyncDrop for Conn {
+    async fn async_drop(self) {
+        self.0.wait().await;
+    }
+}
+
+/// Logs the start and the end of its async drop, yielding in between.
+struct Logged(u32, Arc<Mutex<Vec<String>>>);
+
+impl AsyncDrop for Logged {
+    async fn async_drop(self) {
+        self.1.lock().unwrap().push(format!("start {}", self.0));
+        tokio::task::yield_now().await;
+        self.1.lock().unwrap().push(format!("end {}", self.0));
+    }
+}
+
+struct Faulty;
+
+impl AsyncDrop for Faulty {
+    async fn async_drop(self) {
+        panic!("faulty");
+    }
+}
+
+struct PanicOnDrop;
+
+impl Drop for PanicOnDrop {
+    fn drop(&mut self) {
+        panic!("panic on drop");
+    }
+}
+
+fn conns(n: usize) -> impl Iterator<Item = Conn> {
+    let barrier = Arc::new(Barrier::new(n));
+    (0..n).map(move |_| Conn(Arc::clone(&barrier)))
+}
+
+/// Waits for `handle`, which never resolves unless the elements are dropped
+/// concurrently.
+async fn concurrently(handle: DropHandle) -> bool {
+    let outcome = tokio::time::timeout(Duration::from_secs(5), handle).await;
+    outcome.expect("elements dropped one by one").is_completed()
+}
+
+#[tokio::test]
+async fn vec_drops_concurrently() {
+    let (conns, last_conns) = Arcy::new(conns(3).collect::<Vec<_>>());
+    drop(conns);
+    assert!(concurrently(last_conns).await);
+}
+
+#[tokio::test]
+async fn tuple_drops_concurrently() {
+    let mut conns = conns(3);
+    let mut next = || conns.next().unwrap();
+    let (conns, last_conns) = Arcy::new((next(), next(), next()));
+    drop(conns);
+    assert!(concurrently(last_conns).await);
+}
+
+#[tokio::test]
+async fn sequential_drops_in_order() {
+    let log = Arc::default();
+    let items = (1..=3).map(|i| Logged(i, Arc::clone(&log))).collect();
+    let (items, last_items) = Arcy::new(Sequential::<Vec<_>>(items));
+    drop(items);
+    assert!(last_items.await.is_completed());
+    let log = log.lock().unwrap().clone();
+    let expected = ["start 1", "end 1", "start 2", "end 2", "start 3", "end 3"];
+    assert_eq!(log, expected);
+}
+
+#[tokio::test]
+async fn panicking_element_panics_the_container() {
+    let (conns, last_conns) = Arcy::new(vec![Some(Faulty), None]);
+    drop(conns);
+    assert!(last_conns.await.is_panicked());
+
+    let (pair, last_pair) = Arcy::new((SyncDrop(()), SyncDrop(PanicOnDrop)));
+    drop(pair);
+    assert!(last_pair.await.is_panicked());
+}
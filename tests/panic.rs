@@ -0,0 +1,110 @@
+//! Panics of async drops, and what the panic policy does with them.
+
+#![cfg(feature = "tokio")]
+
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use arcy::{Arcy, AsyncDrop, DropExecutor, DropOutcome, DropStrategy, PanicPolicy};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+
+/// Panics in its async drop, or before even returning its future.
+struct Faulty {
+    eager: bool,
+}
+
+impl AsyncDrop for Faulty {
+    fn async_drop(self) -> impl Future<Output = ()> + Send {
+        if self.eager {
+            panic!("faulty before");
+        }
+        async { panic!("faulty during") }
+    }
+}
+
+#[tokio::test]
+async fn panic_building_the_future_is_caught() {
+    let (faulty, last_faulty) = Arcy::new(Faulty { eager: true });
+    drop(faulty);
+    assert!(last_faulty.await.is_panicked());
+}
+
+#[tokio::test]
+async fn callback_receives_the_payload() {
+    for eager in [true, false] {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let policy = PanicPolicy::callback({
+            let calls = Arc::clone(&calls);
+            move |payload| {
+                let msg = payload.downcast_ref::<&str>().unwrap();
+                assert!(msg.starts_with("faulty"));
+                calls.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        let (faulty, last_faulty) = Arcy::builder().panic_policy(policy).build(Faulty { eager });
+        drop(faulty);
+        assert!(last_faulty.await.is_panicked());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
+
+#[tokio::test]
+async fn resume_panics_in_the_handle() {
+    for eager in [true, false] {
+        let (faulty, last_faulty) = Arcy::builder()
+            .panic_policy(PanicPolicy::Resume)
+            .build(Faulty { eager });
+        drop(faulty);
+        let payload = AssertUnwindSafe(last_faulty).catch_unwind().await;
+        let payload = payload.unwrap_err();
+        assert!(payload
+            .downcast_ref::<&str>()
+            .unwrap()
+            .starts_with("faulty"));
+    }
+}
+
+struct Conn;
+
+impl AsyncDrop for Conn {
+    async fn async_drop(self) {}
+}
+
+/// Spawns on the current runtime, but has a broken timer.
+struct NoTimer;
+
+impl DropExecutor for NoTimer {
+    fn spawn(&self, future: BoxFuture<'static, ()>) -> Result<(), BoxFuture<'static, ()>> {
+        tokio::spawn(future);
+        Ok(())
+    }
+
+    fn sleep(&self, _duration: Duration) -> BoxFuture<'static, ()> {
+        panic!("no timer")
+    }
+}
+
+#[tokio::test]
+async fn inline_poll_panics_are_reported() {
+    let (faulty, last_faulty) = Arcy::builder()
+        .strategy(DropStrategy::Inline)
+        .build(Faulty { eager: true });
+    drop(faulty);
+    assert!(last_faulty.await.is_panicked());
+
+    // the async drop itself is fine, but not its timeout
+    let (conn, last_conn) = Arcy::builder()
+        .executor(NoTimer)
+        .strategy(DropStrategy::Inline)
+        .drop_timeout(Duration::from_secs(1))
+        .build(Conn);
+    drop(conn);
+    match last_conn.await {
+        DropOutcome::Panicked(payload) => assert_eq!(payload.downcast_ref(), Some(&"no timer")),
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+}
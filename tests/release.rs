@@ -0,0 +1,42 @@
+//! What the `DropHandle` of a value released with `Arcy::release` sees.
+
+#![cfg(feature = "tokio")]
+
+use arcy::{Arcy, AsyncDrop, TryAsyncDrop};
+
+/// Fails its async drop.
+struct Tx;
+
+impl TryAsyncDrop for Tx {
+    type Error = &'static str;
+
+    async fn try_async_drop(self) -> Result<(), Self::Error> {
+        Err("rollback failed")
+    }
+}
+
+/// Waits forever in its async drop.
+struct Hung;
+
+impl AsyncDrop for Hung {
+    async fn async_drop(self) {
+        futures::future::pending::<()>().await;
+    }
+}
+
+#[tokio::test]
+async fn error_goes_to_the_caller_only() {
+    let (tx, last_tx) = Arcy::new(Tx);
+    let outcome = Arcy::release(tx).await.unwrap();
+    assert_eq!(outcome.err(), Some("rollback failed"));
+    assert!(last_tx.await.is_cancelled());
+}
+
+#[tokio::test]
+async fn dropping_the_release_cancels_it() {
+    let (hung, last_hung) = Arcy::new(Hung);
+    let mut release = Box::pin(Arcy::release(hung));
+    assert!(futures::poll!(release.as_mut()).is_pending());
+    drop(release);
+    assert!(last_hung.await.is_cancelled());
+}
@@ -0,0 +1,183 @@
+//! What happens to the values whose runtime shuts down before their async drop
+//! is over.
+
+#![cfg(feature = "tokio")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use arcy::{executor, Arcy, AsyncDrop, DropHandle, DropLimiter};
+use futures::executor::block_on;
+use tokio::runtime::{Builder, Runtime};
+
+/// Counts how each connection went away.
+#[derive(Default)]
+struct Counters {
+    started: AtomicUsize,
+    closed: AtomicUsize,
+    fallback: AtomicUsize,
+}
+
+impl Counters {
+    fn get(&self) -> (usize, usize, usize) {
+        (
+            self.started.load(Ordering::SeqCst),
+            self.closed.load(Ordering::SeqCst),
+            self.fallback.load(Ordering::SeqCst),
+        )
+    }
+}
+
+struct Conn {
+    counters: Arc<Counters>,
+    // the async drop never completes
+    hang: bool,
+}
+
+impl AsyncDrop for Conn {
+    async fn async_drop(self) {
+        self.counters.started.fetch_add(1, Ordering::SeqCst);
+        if self.hang {
+            futures::future::pending::<()>().await;
+        }
+        self.counters.closed.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn runtime() -> Runtime {
+    Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+/// Creates a connection whose async drop runs on `runtime`.
+fn conn(runtime: &Runtime, counters: &Arc<Counters>, hang: bool) -> (Arcy<Conn>, DropHandle) {
+    conn_with(runtime, counters, hang, None)
+}
+
+fn conn_with(
+    runtime: &Runtime,
+    counters: &Arc<Counters>,
+    hang: bool,
+    limiter: Option<&DropLimiter>,
+) -> (Arcy<Conn>, DropHandle) {
+    let mut builder = Arcy::builder()
+        .executor(runtime.handle().clone())
+        .sync_fallback(|conn: Conn| {
+            conn.counters.fallback.fetch_add(1, Ordering::SeqCst);
+        });
+    if let Some(limiter) = limiter {
+        builder = builder.limiter(limiter);
+    }
+    let value = Conn {
+        counters: Arc::clone(counters),
+        hang,
+    };
+    builder.build(value)
+}
+
+/// Waits for the spawned async drops to make progress.
+fn settle() {
+    std::thread::sleep(Duration::from_millis(50));
+}
+
+#[test]
+fn completes_before_shutdown() {
+    let runtime = runtime();
+    let counters = Arc::default();
+    let (conn, handle) = conn(&runtime, &counters, false);
+    drop(conn);
+    assert!(runtime.block_on(handle).is_completed());
+    drop(runtime);
+    assert_eq!(counters.get(), (1, 1, 0));
+}
+
+#[test]
+fn released_after_shutdown() {
+    let runtime = runtime();
+    let counters = Arc::default();
+    let (conn, handle) = conn(&runtime, &counters, false);
+    drop(runtime);
+    drop(conn);
+    assert!(block_on(handle).is_cancelled());
+    assert_eq!(counters.get(), (0, 0, 1));
+}
+
+#[test]
+fn released_after_shutdown_from_another_thread() {
+    let runtime = runtime();
+    let counters = Arc::default();
+    let (conn, handle) = conn(&runtime, &counters, false);
+    runtime.shutdown_background();
+    std::thread::spawn(move || drop(conn)).join().unwrap();
+    assert!(block_on(handle).is_cancelled());
+    assert_eq!(counters.get(), (0, 0, 1));
+}
+
+#[test]
+fn shutdown_while_waiting_for_a_permit() {
+    let runtime = runtime();
+    let counters = Arc::default();
+    let limiter = DropLimiter::new(0);
+    let (conn, handle) = conn_with(&runtime, &counters, false, Some(&limiter));
+    drop(conn);
+    settle();
+    assert_eq!(counters.get(), (0, 0, 0));
+    drop(runtime);
+    assert!(block_on(handle).is_cancelled());
+    assert_eq!(counters.get(), (0, 0, 1));
+}
+
+#[test]
+fn shutdown_mid_flight() {
+    let runtime = runtime();
+    let counters = Arc::default();
+    let (conn, handle) = conn(&runtime, &counters, true);
+    drop(conn);
+    settle();
+    assert_eq!(counters.get(), (1, 0, 0));
+    drop(runtime);
+    // the value was moved into the async drop: nothing to fall back on
+    assert!(block_on(handle).is_cancelled());
+    assert_eq!(counters.get(), (1, 0, 0));
+}
+
+#[test]
+fn shutdown_with_many_in_flight() {
+    let runtime = runtime();
+    let counters = Arc::default();
+    let limiter = DropLimiter::new(4);
+    let (conns, handles): (Vec<_>, Vec<_>) = (0..16)
+        .map(|_| conn_with(&runtime, &counters, true, Some(&limiter)))
+        .unzip();
+    drop(conns);
+    settle();
+    drop(runtime);
+    for handle in handles {
+        assert!(block_on(handle).is_cancelled());
+    }
+    // the ones holding a permit had started, the others fell back
+    assert_eq!(counters.get(), (4, 0, 12));
+    assert_eq!(limiter.available_permits(), 4);
+}
+
+#[test]
+fn current_runtime_gone() {
+    let counters = Arc::new(Counters::default());
+    let (conn, handle) = Arcy::builder()
+        .executor(executor::Tokio)
+        .sync_fallback(|conn: Conn| {
+            conn.counters.fallback.fetch_add(1, Ordering::SeqCst);
+        })
+        .build(Conn {
+            counters: Arc::clone(&counters),
+            hang: false,
+        });
+    // no runtime to spawn on
+    drop(conn);
+    assert!(block_on(handle).is_cancelled());
+    assert_eq!(counters.get(), (0, 0, 1));
+}
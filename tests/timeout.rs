@@ -0,0 +1,55 @@
+//! Drop timeouts on an executor without a timer of its own.
+
+#![cfg(all(feature = "thread-pool", target_os = "linux"))]
+
+use std::time::Duration;
+
+use arcy::{Arcy, AsyncDrop};
+use futures::executor::{block_on, ThreadPool};
+
+struct Conn;
+
+impl AsyncDrop for Conn {
+    async fn async_drop(self) {}
+}
+
+struct Hung;
+
+impl AsyncDrop for Hung {
+    async fn async_drop(self) {
+        futures::future::pending::<()>().await;
+    }
+}
+
+fn threads() -> usize {
+    let status = std::fs::read_to_string("/proc/self/status").unwrap();
+    let line = status.lines().find(|line| line.starts_with("Threads:"));
+    line.unwrap()["Threads:".len()..].trim().parse().unwrap()
+}
+
+// a single test: the thread count would be thrown off by tests running alongside
+#[test]
+fn times_out_on_a_shared_timer() {
+    let pool = ThreadPool::builder().pool_size(1).create().unwrap();
+    let (hung, last_hung) = Arcy::builder()
+        .executor(pool.clone())
+        .drop_timeout(Duration::from_millis(10))
+        .build(Hung);
+    drop(hung);
+    assert!(block_on(last_hung).is_timed_out());
+
+    let (conns, handles): (Vec<_>, Vec<_>) = (0..100)
+        .map(|_| {
+            Arcy::builder()
+                .executor(pool.clone())
+                .drop_timeout(Duration::from_secs(30))
+                .build(Conn)
+        })
+        .unzip();
+    let before = threads();
+    drop(conns);
+    for handle in handles {
+        assert!(block_on(handle).is_completed());
+    }
+    assert_eq!(threads(), before);
+}
@@ -0,0 +1,39 @@
+//! Draining a `DropTracker` on an executor without a timer of its own.
+
+#![cfg(feature = "thread-pool")]
+
+use std::time::Duration;
+
+use arcy::{Arcy, AsyncDrop, DropTracker};
+use futures::executor::{block_on, ThreadPool};
+
+struct Conn;
+
+impl AsyncDrop for Conn {
+    async fn async_drop(self) {}
+}
+
+struct Hung;
+
+impl AsyncDrop for Hung {
+    async fn async_drop(self) {
+        futures::future::pending::<()>().await;
+    }
+}
+
+#[test]
+fn drain_times_out() {
+    let pool = ThreadPool::builder().pool_size(1).create().unwrap();
+    let tracker = DropTracker::new();
+    let (conn, _) = Arcy::builder()
+        .executor(pool.clone())
+        .tracker(&tracker)
+        .build(Conn);
+    drop(conn);
+    assert!(block_on(tracker.drain_timeout(Duration::from_secs(30))).is_ok());
+
+    let (hung, _) = Arcy::builder().executor(pool).tracker(&tracker).build(Hung);
+    drop(hung);
+    assert!(block_on(tracker.drain_timeout(Duration::from_millis(10))).is_err());
+    assert_eq!(tracker.pending(), 1);
+}